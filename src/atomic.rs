@@ -0,0 +1,379 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use libc;
+use std;
+use std::cell::Cell;
+use std::os::unix::io;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+
+use ffi;
+use property::{self, PropertyId};
+
+/// Handle of out-fence added to atomic request, used to take the fence after commit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutFence(usize);
+
+/// Value of property change recorded by the request.
+#[derive(Debug)]
+enum Value {
+    Plain(u64),
+    /// In-fence fd kept open as long as the change is part of the request.
+    InFence(io::OwnedFd),
+    /// Index of slot in `out_fences` where kernel writes fence fd.
+    OutFence(usize),
+}
+
+/// Property change recorded by the request. Changes are kept in the same order as in the
+/// underlying `drmModeAtomicReq`, so that they can be replayed into a new request.
+#[derive(Debug)]
+struct Change {
+    object_id: u32,
+    property_id: PropertyId,
+    value: Value,
+}
+
+/// Atomic mode setting request. Collects property changes which are applied all at once by
+/// `drm_mode::atomic_commit`.
+pub struct AtomicRequest {
+    request: ffi::xf86drm_mode::drmModeAtomicReqPtr,
+    changes: Vec<Change>,
+    /// Locations of out-fence fds. Slots are never removed, so `OutFence` handles stay valid and
+    /// pointers passed to kernel stay valid as long as the request exists.
+    #[allow(clippy::vec_box)]
+    out_fences: Vec<Box<Cell<i32>>>,
+}
+
+/// General methods
+impl AtomicRequest {
+    /// Allocate empty request.
+    ///
+    /// Counterpart of `drmModeAtomicAlloc`.
+    pub fn new() -> Self {
+        let request = unsafe { ffi::xf86drm_mode::drmModeAtomicAlloc() };
+        if request.is_null() {
+            panic!("drmModeAtomicAlloc failed");
+        }
+        AtomicRequest {
+            request,
+            changes: Vec::new(),
+            out_fences: Vec::new(),
+        }
+    }
+
+    /// Returns pointer to raw C structure.
+    pub fn as_ptr(&self) -> ffi::xf86drm_mode::drmModeAtomicReqPtr {
+        self.request
+    }
+
+    /// Add change to underlying request and record it.
+    fn push(&mut self, object_id: u32, property_id: PropertyId, value: Value) -> Result<(), i32> {
+        let raw = match value {
+            Value::Plain(value) => value,
+            Value::InFence(ref fence) => fence.as_raw_fd() as u64,
+            Value::OutFence(index) => self.out_fences[index].as_ptr() as u64,
+        };
+        let result = unsafe {
+            ffi::xf86drm_mode::drmModeAtomicAddProperty(self.request, object_id, property_id, raw)
+        };
+        if result < 0 {
+            return Err(result);
+        }
+        self.changes.push(Change {
+            object_id,
+            property_id,
+            value,
+        });
+        Ok(())
+    }
+
+    /// Set property of mode object to `value`.
+    ///
+    /// Counterpart of `drmModeAtomicAddProperty`.
+    pub fn add_property(&mut self,
+                        object_id: u32,
+                        property_id: PropertyId,
+                        value: u64)
+                        -> Result<(), i32> {
+        self.push(object_id, property_id, Value::Plain(value))
+    }
+
+    /// Set property of mode object identified by property name.
+    ///
+    /// Returns `-ENOENT` if the object has no property of that name.
+    pub fn add_property_by_name(&mut self,
+                                fd: io::RawFd,
+                                object_id: u32,
+                                object_type: u32,
+                                name: &str,
+                                value: u64)
+                                -> Result<(), i32> {
+        let property_id = find_property_id(fd, object_id, object_type, name)?;
+        self.add_property(object_id, property_id, value)
+    }
+
+    /// Set fd property, e.g. "IN_FENCE_FD", to sync_file `fence`. The fd is kept open as long
+    /// as the change is part of the request, so the request can be committed more than once.
+    pub fn add_in_fence(&mut self,
+                        object_id: u32,
+                        property_id: PropertyId,
+                        fence: io::OwnedFd)
+                        -> Result<(), i32> {
+        self.push(object_id, property_id, Value::InFence(fence))
+    }
+
+    /// Make plane wait for `fence` before scanning out new framebuffer.
+    ///
+    /// Sets "IN_FENCE_FD" property of the plane.
+    pub fn add_plane_in_fence(&mut self,
+                              fd: io::RawFd,
+                              plane_id: u32,
+                              fence: io::OwnedFd)
+                              -> Result<(), i32> {
+        let property_id =
+            find_property_id(fd, plane_id, ffi::drm_mode::DRM_MODE_OBJECT_PLANE, "IN_FENCE_FD")?;
+        self.add_in_fence(plane_id, property_id, fence)
+    }
+
+    /// Request fence signalled when CRTC starts scanning out the committed state.
+    ///
+    /// Sets "OUT_FENCE_PTR" property of the CRTC. Take the fence with `take_out_fence` after
+    /// commit.
+    pub fn add_crtc_out_fence(&mut self, fd: io::RawFd, crtc_id: u32) -> Result<OutFence, i32> {
+        let property_id =
+            find_property_id(fd, crtc_id, ffi::drm_mode::DRM_MODE_OBJECT_CRTC, "OUT_FENCE_PTR")?;
+        self.add_out_fence(crtc_id, property_id)
+    }
+
+    /// Set pointer property, e.g. "OUT_FENCE_PTR", to location owned by the request where kernel
+    /// writes fence fd during commit. The fence can be taken with `take_out_fence` after commit.
+    pub fn add_out_fence(&mut self,
+                         object_id: u32,
+                         property_id: PropertyId)
+                         -> Result<OutFence, i32> {
+        let index = self.out_fences.len();
+        self.out_fences.push(Box::new(Cell::new(-1)));
+        if let Err(error) = self.push(object_id, property_id, Value::OutFence(index)) {
+            self.out_fences.pop();
+            return Err(error);
+        }
+        Ok(OutFence(index))
+    }
+
+    /// Take fence fd written by kernel during last commit. Returns `None` if the commit did not
+    /// produce fence or fence was already taken. Fences not taken before the next commit are
+    /// closed.
+    pub fn take_out_fence(&mut self, fence: OutFence) -> Option<io::OwnedFd> {
+        let fd = self.out_fences.get(fence.0)?.replace(-1);
+        if fd >= 0 { Some(unsafe { io::OwnedFd::from_raw_fd(fd) }) } else { None }
+    }
+
+    /// Get number of property changes in the request.
+    ///
+    /// Counterpart of `drmModeAtomicGetCursor`.
+    pub fn get_cursor(&self) -> i32 {
+        unsafe { ffi::xf86drm_mode::drmModeAtomicGetCursor(self.request) }
+    }
+
+    /// Drop property changes added after `get_cursor` returned `cursor`. In-fences of dropped
+    /// changes are closed. Cursor beyond number of changes in the request is ignored.
+    ///
+    /// Counterpart of `drmModeAtomicSetCursor`.
+    pub fn set_cursor(&mut self, cursor: i32) {
+        let cursor = cursor.max(0) as usize;
+        if cursor < self.changes.len() {
+            self.changes.truncate(cursor);
+            unsafe { ffi::xf86drm_mode::drmModeAtomicSetCursor(self.request, cursor as i32) };
+        }
+    }
+}
+
+/// Close out-fences of previous commit which were not taken, so that kernel does not overwrite
+/// and leak them during the next commit.
+pub fn rearm_out_fences(request: &AtomicRequest) {
+    for slot in &request.out_fences {
+        let fd = slot.replace(-1);
+        if fd >= 0 {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+/// Find id of property of mode object by name.
+fn find_property_id(fd: io::RawFd,
+                    object_id: u32,
+                    object_type: u32,
+                    name: &str)
+                    -> Result<PropertyId, i32> {
+    property::find_property(fd, object_id, object_type, name)
+        .map(|(property, _)| property.get_prop_id())
+        .ok_or(-libc::ENOENT)
+}
+
+impl Default for AtomicRequest {
+    fn default() -> Self {
+        AtomicRequest::new()
+    }
+}
+
+impl Clone for AtomicRequest {
+    /// Build new request by replaying recorded changes. Out-fences of the copy are written to its
+    /// own slots and in-fences are duplicated, so the copy does not share any fd with `self`.
+    fn clone(&self) -> Self {
+        let mut copy = AtomicRequest::new();
+        copy.out_fences = self.out_fences.iter().map(|_| Box::new(Cell::new(-1))).collect();
+        for change in &self.changes {
+            let value = match change.value {
+                Value::Plain(value) => Value::Plain(value),
+                Value::InFence(ref fence) => {
+                    Value::InFence(fence.try_clone().expect("failed to duplicate fence fd"))
+                }
+                Value::OutFence(index) => Value::OutFence(index),
+            };
+            if copy.push(change.object_id, change.property_id, value).is_err() {
+                panic!("drmModeAtomicAddProperty failed");
+            }
+        }
+        copy
+    }
+}
+
+impl Drop for AtomicRequest {
+    fn drop(&mut self) {
+        for slot in &self.out_fences {
+            if slot.get() >= 0 {
+                unsafe { libc::close(slot.get()) };
+            }
+        }
+        unsafe { ffi::xf86drm_mode::drmModeAtomicFree(self.request) };
+    }
+}
+
+impl std::fmt::Debug for AtomicRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "AtomicRequest {{ cursor: {} }}", self.get_cursor())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libc;
+    use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd};
+
+    use super::{AtomicRequest, Value, rearm_out_fences};
+
+    /// Create pipe. Returns read and write end.
+    fn pipe() -> (OwnedFd, OwnedFd) {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) }, 0);
+        unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) }
+    }
+
+    /// Check if read end of pipe is still open by writing to its write end.
+    fn has_reader(write: &OwnedFd) -> bool {
+        unsafe { libc::write(write.as_raw_fd(), b"x".as_ptr() as *const _, 1) == 1 }
+    }
+
+    fn in_fence_fd(request: &AtomicRequest, index: usize) -> i32 {
+        match request.changes[index].value {
+            Value::InFence(ref fence) => fence.as_raw_fd(),
+            ref value => panic!("unexpected value {:?}", value),
+        }
+    }
+
+    #[test]
+    fn clone_does_not_share_fences() {
+        let (read, write) = pipe();
+        let mut request = AtomicRequest::new();
+        request.add_property(1, 2, 3).unwrap();
+        request.add_in_fence(4, 5, read).unwrap();
+        let fence = request.add_out_fence(6, 7).unwrap();
+
+        let mut copy = request.clone();
+        assert_eq!(copy.get_cursor(), 3);
+        assert_ne!(in_fence_fd(&copy, 1), in_fence_fd(&request, 1));
+        assert_ne!(copy.out_fences[0].as_ptr(), request.out_fences[0].as_ptr());
+
+        // Fence written to original request is not visible through the copy.
+        let (fake, _) = pipe();
+        request.out_fences[0].set(unsafe { libc::dup(fake.as_raw_fd()) });
+        assert!(copy.take_out_fence(fence).is_none());
+        assert!(request.take_out_fence(fence).is_some());
+
+        // Duplicated in-fence stays open after original request is gone.
+        drop(request);
+        assert!(has_reader(&write));
+        drop(copy);
+        assert!(!has_reader(&write));
+    }
+
+    #[test]
+    fn set_cursor_closes_dropped_in_fences() {
+        let (read, write) = pipe();
+        let mut request = AtomicRequest::new();
+        request.add_property(1, 2, 3).unwrap();
+        let cursor = request.get_cursor();
+        request.add_in_fence(4, 5, read).unwrap();
+        request.add_out_fence(6, 7).unwrap();
+        assert_eq!(request.get_cursor(), 3);
+
+        request.set_cursor(cursor);
+        assert_eq!(request.get_cursor(), 1);
+        assert!(!has_reader(&write));
+        assert_eq!(request.clone().get_cursor(), 1);
+
+        // Dropped changes can not be brought back.
+        request.set_cursor(3);
+        assert_eq!(request.get_cursor(), 1);
+        request.set_cursor(-1);
+        assert_eq!(request.get_cursor(), 0);
+    }
+
+    #[test]
+    fn out_fence_ownership() {
+        let mut request = AtomicRequest::new();
+        let first = request.add_out_fence(1, 2).unwrap();
+        let second = request.add_out_fence(3, 4).unwrap();
+        assert!(request.take_out_fence(first).is_none());
+
+        // Simulate kernel writing fences during commit.
+        let (read_first, write_first) = pipe();
+        let (read_second, write_second) = pipe();
+        request.out_fences[first.0].set(read_first.into_raw_fd());
+        request.out_fences[second.0].set(read_second.into_raw_fd());
+
+        let taken = request.take_out_fence(first).unwrap();
+        assert!(request.take_out_fence(first).is_none());
+        drop(request);
+        assert!(has_reader(&write_first));
+        assert!(!has_reader(&write_second));
+        drop(taken);
+        assert!(!has_reader(&write_first));
+    }
+
+    #[test]
+    fn rearm_closes_untaken_out_fences() {
+        let mut request = AtomicRequest::new();
+        let fence = request.add_out_fence(1, 2).unwrap();
+        let (read, write) = pipe();
+        request.out_fences[fence.0].set(read.into_raw_fd());
+
+        rearm_out_fences(&request);
+        assert!(!has_reader(&write));
+        assert!(request.take_out_fence(fence).is_none());
+    }
+}
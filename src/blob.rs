@@ -0,0 +1,99 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::mem;
+use std::os::unix::io;
+use std::slice;
+
+use ffi;
+use drm_mode;
+use mode_info::ModeInfo;
+
+/// Property blob owned by this client. The blob is destroyed when dropped.
+///
+/// Kernel keeps blob alive while it is used as value of some property, so it is safe to drop
+/// `PropertyBlob` after committing it.
+#[derive(Debug)]
+pub struct PropertyBlob {
+    fd: io::RawFd,
+    id: u32,
+}
+
+/// General methods
+impl PropertyBlob {
+    /// Create blob holding copy of `data`.
+    pub fn new(fd: io::RawFd, data: &[u8]) -> Result<Self, i32> {
+        let id = drm_mode::create_property_blob(fd, data)?;
+        Ok(PropertyBlob { fd, id })
+    }
+
+    /// Create blob holding copy of `value`, e.g. `hdr_output_metadata`.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be `#[repr(C)]` structure without padding or pointers.
+    pub unsafe fn from_value<T>(fd: io::RawFd, value: &T) -> Result<Self, i32> {
+        let data = slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>());
+        PropertyBlob::new(fd, data)
+    }
+
+    /// Create blob holding mode, to be used as "MODE_ID" property of CRTC.
+    pub fn from_mode(fd: io::RawFd, mode: &ModeInfo) -> Result<Self, i32> {
+        unsafe { PropertyBlob::from_value(fd, &*mode.as_ptr()) }
+    }
+
+    /// Release ownership of the blob without destroying it.
+    pub fn into_raw(self) -> u32 {
+        let id = self.id;
+        mem::forget(self);
+        id
+    }
+}
+
+/// Getters
+impl PropertyBlob {
+    #[inline]
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Drop for PropertyBlob {
+    fn drop(&mut self) {
+        let _ = drm_mode::destroy_property_blob(self.fd, self.id);
+    }
+}
+
+/// Read contents of property blob.
+///
+/// Counterpart of `drmModeGetPropertyBlob`.
+pub fn get_property_blob(fd: io::RawFd, blob_id: u32) -> Option<Vec<u8>> {
+    let blob = unsafe { ffi::xf86drm_mode::drmModeGetPropertyBlob(fd, blob_id) };
+    if blob.is_null() {
+        return None;
+    }
+
+    let data = unsafe {
+        if (*blob).data.is_null() {
+            Vec::new()
+        } else {
+            slice::from_raw_parts((*blob).data as *const u8, (*blob).length as usize).to_vec()
+        }
+    };
+    unsafe { ffi::xf86drm_mode::drmModeFreePropertyBlob(blob) };
+    Some(data)
+}
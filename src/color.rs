@@ -0,0 +1,272 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Color management of CRTCs through atomic properties.
+//!
+//! CRTC pipeline applies "DEGAMMA_LUT" to linearize pixels, then multiplies them by color
+//! transformation matrix "CTM" and finally applies "GAMMA_LUT". Each of them is set to id of
+//! property blob holding the data, created with `create_blob`, or zero to bypass the stage.
+
+use std::mem;
+use std::os::unix::io;
+use std::slice;
+
+use ffi;
+use drm_mode;
+use blob::PropertyBlob;
+use crtc::CrtcId;
+use gamma::GammaLut;
+use property;
+
+/// Entry of `ColorLut`. Values are in U0.16 fixed point format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ColorLutEntry {
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+}
+
+/// Lookup table used as "DEGAMMA_LUT" or "GAMMA_LUT" property of CRTC.
+///
+/// Should have as many entries as reported by "DEGAMMA_LUT_SIZE" or "GAMMA_LUT_SIZE"
+/// respectively, see `get_degamma_lut_size` and `get_gamma_lut_size`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorLut {
+    pub entries: Vec<ColorLutEntry>,
+}
+
+/// Constructors
+impl ColorLut {
+    /// Create table which does not change colors.
+    pub fn identity(size: usize) -> Self {
+        ColorLut::from(&GammaLut::identity(size))
+    }
+
+    /// Create table from function mapping input in range `[0, 1]` to output of each channel in
+    /// the same range.
+    pub fn from_fn<F>(size: usize, function: F) -> Self
+        where F: Fn(f64) -> (f64, f64, f64)
+    {
+        ColorLut::from(&GammaLut::from_fn(size, function))
+    }
+}
+
+/// General methods
+impl ColorLut {
+    /// Serialize as array of `drm_color_lut` suitable for property blob.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let raw: Vec<ffi::drm_mode::drm_color_lut> = self.entries
+            .iter()
+            .map(|entry| {
+                ffi::drm_mode::drm_color_lut {
+                    red: entry.red,
+                    green: entry.green,
+                    blue: entry.blue,
+                    reserved: 0,
+                }
+            })
+            .collect();
+        as_bytes(&raw).to_vec()
+    }
+
+    /// Create property blob holding the table.
+    pub fn create_blob(&self, fd: io::RawFd) -> Result<PropertyBlob, i32> {
+        PropertyBlob::new(fd, &self.to_bytes())
+    }
+}
+
+impl<'a> From<&'a GammaLut> for ColorLut {
+    /// Convert legacy gamma ramp. Channels of different lengths are truncated to the shortest.
+    fn from(lut: &'a GammaLut) -> Self {
+        let entries = lut.red
+            .iter()
+            .zip(lut.green.iter())
+            .zip(lut.blue.iter())
+            .map(|((&red, &green), &blue)| ColorLutEntry { red, green, blue })
+            .collect();
+        ColorLut { entries }
+    }
+}
+
+/// Color transformation matrix used as "CTM" property of CRTC.
+///
+/// Output color is `matrix * [red, green, blue]`, with matrix in row-major order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorMatrix {
+    pub matrix: [[f64; 3]; 3],
+}
+
+/// Constructors
+impl ColorMatrix {
+    /// Create matrix which does not change colors.
+    pub fn identity() -> Self {
+        ColorMatrix { matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]] }
+    }
+
+    /// Create matrix scaling each channel independently.
+    pub fn from_scale(red: f64, green: f64, blue: f64) -> Self {
+        ColorMatrix { matrix: [[red, 0.0, 0.0], [0.0, green, 0.0], [0.0, 0.0, blue]] }
+    }
+
+    /// Decode matrix from `drm_color_ctm` values.
+    pub fn from_raw(raw: &[u64; 9]) -> Self {
+        let mut matrix = [[0.0; 3]; 3];
+        for (i, value) in raw.iter().enumerate() {
+            matrix[i / 3][i % 3] = from_s31_32(*value);
+        }
+        ColorMatrix { matrix }
+    }
+}
+
+/// General methods
+impl ColorMatrix {
+    /// Encode matrix as `drm_color_ctm` values in S31.32 sign-magnitude format.
+    pub fn to_raw(&self) -> [u64; 9] {
+        let mut raw = [0; 9];
+        for (i, value) in raw.iter_mut().enumerate() {
+            *value = to_s31_32(self.matrix[i / 3][i % 3]);
+        }
+        raw
+    }
+
+    /// Serialize as `drm_color_ctm` suitable for property blob.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let ctm = ffi::drm_mode::drm_color_ctm { matrix: self.to_raw() };
+        as_bytes(slice::from_ref(&ctm)).to_vec()
+    }
+
+    /// Create property blob holding the matrix.
+    pub fn create_blob(&self, fd: io::RawFd) -> Result<PropertyBlob, i32> {
+        PropertyBlob::new(fd, &self.to_bytes())
+    }
+}
+
+impl From<[[f64; 3]; 3]> for ColorMatrix {
+    fn from(matrix: [[f64; 3]; 3]) -> Self {
+        ColorMatrix { matrix }
+    }
+}
+
+/// Sign bit of S31.32 sign-magnitude value.
+const S31_32_SIGN: u64 = 1 << 63;
+
+/// Encode value in S31.32 sign-magnitude format. Out of range values are saturated.
+fn to_s31_32(value: f64) -> u64 {
+    let magnitude = (value.abs() * (1u64 << 32) as f64).round();
+    let magnitude = if magnitude >= (S31_32_SIGN - 1) as f64 {
+        S31_32_SIGN - 1
+    } else {
+        magnitude as u64
+    };
+    if value.is_sign_negative() && magnitude != 0 {
+        magnitude | S31_32_SIGN
+    } else {
+        magnitude
+    }
+}
+
+/// Decode value in S31.32 sign-magnitude format.
+fn from_s31_32(value: u64) -> f64 {
+    let magnitude = (value & !S31_32_SIGN) as f64 / (1u64 << 32) as f64;
+    if value & S31_32_SIGN != 0 { -magnitude } else { magnitude }
+}
+
+/// View slice of plain C structures as bytes.
+fn as_bytes<T>(data: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data)) }
+}
+
+/// Get number of entries of "DEGAMMA_LUT" supported by CRTC.
+///
+/// Returns `None` if CRTC does not support color management.
+pub fn get_degamma_lut_size(fd: io::RawFd, crtc_id: CrtcId) -> Option<usize> {
+    get_crtc_property_value(fd, crtc_id, "DEGAMMA_LUT_SIZE").map(|size| size as usize)
+}
+
+/// Get number of entries of "GAMMA_LUT" supported by CRTC.
+///
+/// Returns `None` if CRTC does not support color management.
+pub fn get_gamma_lut_size(fd: io::RawFd, crtc_id: CrtcId) -> Option<usize> {
+    get_crtc_property_value(fd, crtc_id, "GAMMA_LUT_SIZE").map(|size| size as usize)
+}
+
+/// Get current value of CRTC property.
+fn get_crtc_property_value(fd: io::RawFd, crtc_id: CrtcId, name: &str) -> Option<u64> {
+    property::find_property(fd, crtc_id, drm_mode::OBJECT_CRTC, name).map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_s31_32, to_s31_32, ColorLut, ColorLutEntry, ColorMatrix, S31_32_SIGN};
+
+    #[test]
+    fn s31_32_round_trips() {
+        assert_eq!(to_s31_32(0.0), 0);
+        assert_eq!(to_s31_32(-0.0), 0);
+        assert_eq!(to_s31_32(1.0), 1 << 32);
+        assert_eq!(to_s31_32(-1.0), S31_32_SIGN | 1 << 32);
+        assert_eq!(to_s31_32(0.5), 1 << 31);
+        assert_eq!(to_s31_32(-0.5), S31_32_SIGN | 1 << 31);
+        for value in &[-1.0, 0.5, -0.5, 1.0, 0.0, 2.25, -1234.125] {
+            assert_eq!(from_s31_32(to_s31_32(*value)), *value);
+        }
+    }
+
+    #[test]
+    fn s31_32_saturates() {
+        assert_eq!(to_s31_32(1e12), S31_32_SIGN - 1);
+        assert_eq!(to_s31_32(-1e12), u64::MAX);
+        assert_eq!(to_s31_32(f64::INFINITY), S31_32_SIGN - 1);
+        assert!(from_s31_32(to_s31_32(1e12)) > 2147483647.0);
+        assert!(from_s31_32(to_s31_32(-1e12)) < -2147483647.0);
+    }
+
+    #[test]
+    fn matrix_round_trips() {
+        let matrix = ColorMatrix::from([[1.0, -1.0, 0.5], [0.0, 0.25, -0.5], [2.0, 0.0, 1.0]]);
+        let raw = matrix.to_raw();
+        assert_eq!(raw[1], S31_32_SIGN | 1 << 32);
+        assert_eq!(raw[2], 1 << 31);
+        assert_eq!(ColorMatrix::from_raw(&raw), matrix);
+        assert_eq!(ColorMatrix::identity().to_bytes().len(), 9 * 8);
+        assert_eq!(ColorMatrix::from_raw(&ColorMatrix::identity().to_raw()),
+                   ColorMatrix::identity());
+    }
+
+    #[test]
+    fn lut_scales_to_16_bits() {
+        let lut = ColorLut::from_fn(3, |value| (value, 1.0 - value, 0.5));
+        assert_eq!(lut.entries,
+                   vec![ColorLutEntry { red: 0, green: 0xffff, blue: 0x8000 },
+                        ColorLutEntry { red: 0x8000, green: 0x8000, blue: 0x8000 },
+                        ColorLutEntry { red: 0xffff, green: 0, blue: 0x8000 }]);
+        assert_eq!(ColorLut::identity(2).entries[1],
+                   ColorLutEntry { red: 0xffff, green: 0xffff, blue: 0xffff });
+    }
+
+    #[test]
+    fn lut_serializes_as_drm_color_lut() {
+        let entry = ColorLutEntry { red: 0x0102, green: 0x0304, blue: 0x0506 };
+        let lut = ColorLut { entries: vec![entry] };
+        let bytes = lut.to_bytes();
+        assert_eq!(bytes.len(), 8);
+        let words: Vec<u16> = bytes.chunks(2)
+            .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+            .collect();
+        assert_eq!(words, vec![0x0102, 0x0304, 0x0506, 0]);
+    }
+}
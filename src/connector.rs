@@ -67,6 +67,11 @@ impl Connector {
         Connector { connector: connector }
     }
 
+    /// Check if connector is writeback connector, capturing output of CRTC into framebuffer.
+    pub fn is_writeback(&self) -> bool {
+        self.get_connector_type() == ffi::xf86drm_mode::DRM_MODE_CONNECTOR_WRITEBACK as u32
+    }
+
     /// Get string representation of connector type.
     pub fn get_type_name(&self) -> &'static str {
         let connector_type = self.get_connector_type() as usize;
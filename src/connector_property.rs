@@ -0,0 +1,315 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Typed access to common enum and range properties of connectors.
+//!
+//! Values are validated against entries and ranges reported by the driver before writing.
+
+use libc;
+use std::os::unix::io;
+
+use atomic::AtomicRequest;
+use connector::ConnectorId;
+use drm_mode;
+use property::{self, Property};
+
+/// Connector property whose values are enum entries.
+pub trait EnumProperty: Copy + PartialEq + Sized + 'static {
+    /// Name of the property.
+    const NAME: &'static str;
+
+    /// Values paired with names of corresponding enum entries.
+    fn entries() -> &'static [(Self, &'static str)];
+
+    /// Get name of enum entry.
+    fn entry_name(&self) -> &'static str {
+        Self::entries().iter().find(|&&(value, _)| value == *self).unwrap().1
+    }
+
+    /// Get value from name of enum entry.
+    fn from_entry_name(name: &str) -> Option<Self> {
+        Self::entries().iter().find(|&&(_, n)| n == name).map(|&(value, _)| value)
+    }
+}
+
+/// How mode is scaled if it does not match native resolution of panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalingMode {
+    None,
+    Full,
+    Center,
+    FullAspect,
+}
+
+impl EnumProperty for ScalingMode {
+    const NAME: &'static str = "scaling mode";
+
+    fn entries() -> &'static [(Self, &'static str)] {
+        &[(ScalingMode::None, "None"),
+          (ScalingMode::Full, "Full"),
+          (ScalingMode::Center, "Center"),
+          (ScalingMode::FullAspect, "Full aspect")]
+    }
+}
+
+/// Underscan of image to compensate overscan of TVs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Underscan {
+    Off,
+    On,
+    Auto,
+}
+
+impl EnumProperty for Underscan {
+    const NAME: &'static str = "underscan";
+
+    fn entries() -> &'static [(Self, &'static str)] {
+        &[(Underscan::Off, "off"), (Underscan::On, "on"), (Underscan::Auto, "auto")]
+    }
+}
+
+/// Quantization range of RGB output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BroadcastRgb {
+    Automatic,
+    Full,
+    /// Limited range 16 to 235.
+    Limited,
+}
+
+impl EnumProperty for BroadcastRgb {
+    const NAME: &'static str = "Broadcast RGB";
+
+    fn entries() -> &'static [(Self, &'static str)] {
+        &[(BroadcastRgb::Automatic, "Automatic"),
+          (BroadcastRgb::Full, "Full"),
+          (BroadcastRgb::Limited, "Limited 16:235")]
+    }
+}
+
+/// Type of content sent to display, used by display to choose processing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentType {
+    NoData,
+    Graphics,
+    Photo,
+    Cinema,
+    Game,
+}
+
+impl EnumProperty for ContentType {
+    const NAME: &'static str = "content type";
+
+    fn entries() -> &'static [(Self, &'static str)] {
+        &[(ContentType::NoData, "No Data"),
+          (ContentType::Graphics, "Graphics"),
+          (ContentType::Photo, "Photo"),
+          (ContentType::Cinema, "Cinema"),
+          (ContentType::Game, "Game")]
+    }
+}
+
+/// Dithering of output when panel has fewer bits per channel than framebuffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dithering {
+    Off,
+    On,
+    /// Driver decides based on depth of panel.
+    Automatic,
+}
+
+impl EnumProperty for Dithering {
+    const NAME: &'static str = "dithering";
+
+    fn entries() -> &'static [(Self, &'static str)] {
+        &[(Dithering::Off, "Off"), (Dithering::On, "On"), (Dithering::Automatic, "Automatic")]
+    }
+}
+
+/// Picture aspect ratio signalled to display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AspectRatio {
+    Automatic,
+    Ratio4To3,
+    Ratio16To9,
+}
+
+impl EnumProperty for AspectRatio {
+    const NAME: &'static str = "aspect ratio";
+
+    fn entries() -> &'static [(Self, &'static str)] {
+        &[(AspectRatio::Automatic, "Automatic"),
+          (AspectRatio::Ratio4To3, "4:3"),
+          (AspectRatio::Ratio16To9, "16:9")]
+    }
+}
+
+/// Find property of connector with its current value.
+fn find(fd: io::RawFd, connector_id: ConnectorId, name: &str) -> Option<(Property, u64)> {
+    property::find_property(fd, connector_id, drm_mode::OBJECT_CONNECTOR, name)
+}
+
+/// Find property of connector and check that `value` is within its range.
+///
+/// Returns `-ENOENT` if property does not exist and `-EINVAL` if value is out of range.
+fn find_range(fd: io::RawFd,
+              connector_id: ConnectorId,
+              name: &str,
+              value: u64)
+              -> Result<Property, i32> {
+    let (property, _) = find(fd, connector_id, name).ok_or(-libc::ENOENT)?;
+    match property.get_values().as_slice() {
+        &[min, max] if min <= value && value <= max => Ok(property),
+        _ => Err(-libc::EINVAL),
+    }
+}
+
+/// Find property of connector and value of enum entry.
+///
+/// Returns `-ENOENT` if property does not exist and `-EINVAL` if driver does not support the entry.
+fn find_enum<T: EnumProperty>(fd: io::RawFd,
+                              connector_id: ConnectorId,
+                              value: T)
+                              -> Result<(Property, u64), i32> {
+    let (property, _) = find(fd, connector_id, T::NAME).ok_or(-libc::ENOENT)?;
+    let raw = property.find_enum(value.entry_name()).ok_or(-libc::EINVAL)?;
+    Ok((property, raw))
+}
+
+/// Get current value of enum property of connector.
+pub fn get_connector_enum<T: EnumProperty>(fd: io::RawFd, connector_id: ConnectorId) -> Option<T> {
+    let (property, raw) = find(fd, connector_id, T::NAME)?;
+    let entry = property.get_enums().into_iter().find(|entry| entry.value == raw)?;
+    T::from_entry_name(&entry.name)
+}
+
+/// Get values of enum property supported by connector.
+pub fn get_connector_enum_values<T: EnumProperty>(fd: io::RawFd,
+                                                  connector_id: ConnectorId)
+                                                  -> Vec<T> {
+    find(fd, connector_id, T::NAME)
+        .map(|(property, _)| {
+            property.get_enums()
+                .iter()
+                .filter_map(|entry| T::from_entry_name(&entry.name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Set enum property of connector without atomic request.
+pub fn set_connector_enum<T: EnumProperty>(fd: io::RawFd,
+                                           connector_id: ConnectorId,
+                                           value: T)
+                                           -> Result<(), i32> {
+    let (property, raw) = find_enum(fd, connector_id, value)?;
+    drm_mode::set_object_property(fd,
+                                  connector_id,
+                                  drm_mode::OBJECT_CONNECTOR,
+                                  property.get_prop_id(),
+                                  raw)
+}
+
+/// Add change of enum property of connector to atomic request.
+pub fn add_connector_enum<T: EnumProperty>(request: &mut AtomicRequest,
+                                           fd: io::RawFd,
+                                           connector_id: ConnectorId,
+                                           value: T)
+                                           -> Result<(), i32> {
+    let (property, raw) = find_enum(fd, connector_id, value)?;
+    request.add_property(connector_id, property.get_prop_id(), raw)
+}
+
+/// Get horizontal and vertical underscan borders in pixels.
+pub fn get_underscan_borders(fd: io::RawFd, connector_id: ConnectorId) -> Option<(u32, u32)> {
+    let (_, hborder) = find(fd, connector_id, "underscan hborder")?;
+    let (_, vborder) = find(fd, connector_id, "underscan vborder")?;
+    Some((hborder as u32, vborder as u32))
+}
+
+/// Set horizontal and vertical underscan borders in pixels. Used if underscan is enabled.
+pub fn set_underscan_borders(fd: io::RawFd,
+                             connector_id: ConnectorId,
+                             hborder: u32,
+                             vborder: u32)
+                             -> Result<(), i32> {
+    let hproperty = find_range(fd, connector_id, "underscan hborder", hborder as u64)?;
+    let vproperty = find_range(fd, connector_id, "underscan vborder", vborder as u64)?;
+    drm_mode::set_object_property(fd,
+                                  connector_id,
+                                  drm_mode::OBJECT_CONNECTOR,
+                                  hproperty.get_prop_id(),
+                                  hborder as u64)?;
+    drm_mode::set_object_property(fd,
+                                  connector_id,
+                                  drm_mode::OBJECT_CONNECTOR,
+                                  vproperty.get_prop_id(),
+                                  vborder as u64)
+}
+
+/// Get range of bits per color channel supported by connector.
+pub fn get_max_bpc_range(fd: io::RawFd, connector_id: ConnectorId) -> Option<(u32, u32)> {
+    let (property, _) = find(fd, connector_id, "max bpc")?;
+    match property.get_values().as_slice() {
+        &[min, max] => Some((min as u32, max as u32)),
+        _ => None,
+    }
+}
+
+/// Get upper limit of bits per color channel.
+pub fn get_max_bpc(fd: io::RawFd, connector_id: ConnectorId) -> Option<u32> {
+    find(fd, connector_id, "max bpc").map(|(_, value)| value as u32)
+}
+
+/// Set upper limit of bits per color channel without atomic request.
+pub fn set_max_bpc(fd: io::RawFd, connector_id: ConnectorId, bpc: u32) -> Result<(), i32> {
+    let property = find_range(fd, connector_id, "max bpc", bpc as u64)?;
+    drm_mode::set_object_property(fd,
+                                  connector_id,
+                                  drm_mode::OBJECT_CONNECTOR,
+                                  property.get_prop_id(),
+                                  bpc as u64)
+}
+
+/// Add change of upper limit of bits per color channel to atomic request.
+pub fn add_max_bpc(request: &mut AtomicRequest,
+                   fd: io::RawFd,
+                   connector_id: ConnectorId,
+                   bpc: u32)
+                   -> Result<(), i32> {
+    let property = find_range(fd, connector_id, "max bpc", bpc as u64)?;
+    request.add_property(connector_id, property.get_prop_id(), bpc as u64)
+}
+
+/// Set dithering of connector without atomic request.
+///
+/// Returns `-ENOENT` if driver does not support dithering control.
+pub fn set_dithering(fd: io::RawFd,
+                     connector_id: ConnectorId,
+                     dithering: Dithering)
+                     -> Result<(), i32> {
+    set_connector_enum(fd, connector_id, dithering)
+}
+
+/// Add change of dithering of connector to atomic request.
+pub fn add_dithering(request: &mut AtomicRequest,
+                     fd: io::RawFd,
+                     connector_id: ConnectorId,
+                     dithering: Dithering)
+                     -> Result<(), i32> {
+    add_connector_enum(request, fd, connector_id, dithering)
+}
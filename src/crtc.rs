@@ -18,6 +18,7 @@
 use std;
 
 use ffi;
+use mode_info::ModeInfo;
 
 /// Type of CRTC id.
 pub type CrtcId = u32;
@@ -75,6 +76,12 @@ impl Crtc {
         unsafe { (*self.crtc).mode_valid != 0 }
     }
 
+    /// Get current mode. Valid only if `get_mode_valid` returns `true`.
+    #[inline]
+    pub fn get_mode(&self) -> ModeInfo {
+        ModeInfo::new(unsafe { (*self.crtc).mode.clone() })
+    }
+
     #[inline]
     pub fn get_gamma_size(&self) -> isize {
         unsafe { (*self.crtc).gamma_size as isize }
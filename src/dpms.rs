@@ -0,0 +1,129 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Power state of displays and state of connector links.
+//!
+//! Legacy drivers control power of display through "DPMS" property of connector. With atomic
+//! modesetting "DPMS" is emulated and "ACTIVE" property of CRTC should be used instead.
+
+use libc;
+use std::os::unix::io;
+
+use ffi;
+use atomic::AtomicRequest;
+use connector::ConnectorId;
+use crtc::CrtcId;
+use drm_mode;
+use property;
+
+/// Power state of display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DpmsState {
+    On = ffi::drm_mode::DRM_MODE_DPMS_ON as isize,
+    Standby = ffi::drm_mode::DRM_MODE_DPMS_STANDBY as isize,
+    Suspend = ffi::drm_mode::DRM_MODE_DPMS_SUSPEND as isize,
+    Off = ffi::drm_mode::DRM_MODE_DPMS_OFF as isize,
+}
+
+/// State of link between connector and display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkStatus {
+    Good,
+    /// Link failed, e.g. DisplayPort link training failed. Userspace should probe modes again
+    /// and do modeset.
+    Bad,
+}
+
+/// Get power state of display connected to connector.
+pub fn get_dpms(fd: io::RawFd, connector_id: ConnectorId) -> Option<DpmsState> {
+    let (_, value) = property::find_property(fd, connector_id, drm_mode::OBJECT_CONNECTOR, "DPMS")?;
+    match value as libc::c_int {
+        ffi::drm_mode::DRM_MODE_DPMS_ON => Some(DpmsState::On),
+        ffi::drm_mode::DRM_MODE_DPMS_STANDBY => Some(DpmsState::Standby),
+        ffi::drm_mode::DRM_MODE_DPMS_SUSPEND => Some(DpmsState::Suspend),
+        ffi::drm_mode::DRM_MODE_DPMS_OFF => Some(DpmsState::Off),
+        _ => None,
+    }
+}
+
+/// Set power state of display connected to connector through "DPMS" property.
+///
+/// Counterpart of `drmModeConnectorSetProperty`.
+pub fn set_dpms(fd: io::RawFd, connector_id: ConnectorId, state: DpmsState) -> Result<(), i32> {
+    let (property, _) =
+        property::find_property(fd, connector_id, drm_mode::OBJECT_CONNECTOR, "DPMS")
+            .ok_or(-libc::ENOENT)?;
+    let result = unsafe {
+        ffi::xf86drm_mode::drmModeConnectorSetProperty(fd,
+                                                       connector_id,
+                                                       property.get_prop_id(),
+                                                       state as u64)
+    };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Add change of "ACTIVE" property of CRTC to atomic request. Changing it requires
+/// `ATOMIC_ALLOW_MODESET`.
+pub fn add_active(request: &mut AtomicRequest,
+                  fd: io::RawFd,
+                  crtc_id: CrtcId,
+                  active: bool)
+                  -> Result<(), i32> {
+    request.add_property_by_name(fd, crtc_id, drm_mode::OBJECT_CRTC, "ACTIVE", active as u64)
+}
+
+/// Get state of link of connector. Returns `None` if connector does not report it.
+pub fn get_link_status(fd: io::RawFd, connector_id: ConnectorId) -> Option<LinkStatus> {
+    let (_, value) =
+        property::find_property(fd, connector_id, drm_mode::OBJECT_CONNECTOR, "link-status")?;
+    match value as libc::c_int {
+        ffi::drm_mode::DRM_MODE_LINK_STATUS_GOOD => Some(LinkStatus::Good),
+        ffi::drm_mode::DRM_MODE_LINK_STATUS_BAD => Some(LinkStatus::Bad),
+        _ => None,
+    }
+}
+
+/// Add reset of "link-status" property of connector to atomic request. Committing it with
+/// `ATOMIC_ALLOW_MODESET` retrains the link.
+pub fn add_link_status_good(request: &mut AtomicRequest,
+                            fd: io::RawFd,
+                            connector_id: ConnectorId)
+                            -> Result<(), i32> {
+    request.add_property_by_name(fd,
+                                 connector_id,
+                                 drm_mode::OBJECT_CONNECTOR,
+                                 "link-status",
+                                 ffi::drm_mode::DRM_MODE_LINK_STATUS_GOOD as u64)
+}
+
+/// Retrain link of connectors driven by CRTC by setting its current mode again. Legacy
+/// counterpart of committing `add_link_status_good`.
+///
+/// Returns `-EINVAL` if CRTC has no mode set.
+pub fn retrain_link(fd: io::RawFd, crtc_id: CrtcId, connectors: &[ConnectorId]) -> Result<(), i32> {
+    let crtc = drm_mode::get_crtc(fd, crtc_id).ok_or(-libc::ENOENT)?;
+    if !crtc.get_mode_valid() {
+        return Err(-libc::EINVAL);
+    }
+    drm_mode::set_crtc(fd,
+                       crtc_id,
+                       crtc.get_buffer_id(),
+                       crtc.get_x(),
+                       crtc.get_y(),
+                       connectors,
+                       &crtc.get_mode())
+}
@@ -15,11 +15,28 @@
 // DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
+use libc;
+use std::ffi::{CStr, OsStr};
+use std::ops;
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::io;
+use std::os::unix::io::FromRawFd;
+use std::path::PathBuf;
 
 use ffi;
 
 pub use event_handler::{EventContext, handle_event};
+pub use event::{Event, VBlankEvent, parse_events, read_events};
+pub use version::Version;
+pub use gem::{GemHandle, GemName};
+pub use uevent::{Uevent, UeventSocket, parse_uevent};
+pub use hotplug::{HotplugEvent, HotplugMonitor};
+
+#[cfg(feature = "tokio")]
+pub use event::EventStream;
+
+#[cfg(feature = "calloop")]
+pub use event::EventSource;
 
 #[repr(u64)]
 pub enum Capability {
@@ -35,6 +52,77 @@ pub enum Capability {
     AddFB2Modifiers = ffi::drm::DRM_CAP_ADDFB2_MODIFIERS,
 }
 
+/// Capabilities which client can enable with `set_client_cap`.
+#[repr(u64)]
+pub enum ClientCapability {
+    Stereo3D = ffi::drm::DRM_CLIENT_CAP_STEREO_3D as u64,
+    UniversalPlanes = ffi::drm::DRM_CLIENT_CAP_UNIVERSAL_PLANES as u64,
+    /// Enables atomic mode setting. Implies `UniversalPlanes`.
+    Atomic = ffi::drm::DRM_CLIENT_CAP_ATOMIC as u64,
+    /// Exposes writeback connectors. Requires `Atomic`.
+    WritebackConnectors = ffi::drm::DRM_CLIENT_CAP_WRITEBACK_CONNECTORS as u64,
+}
+
+/// Flags for `prime_handle_to_fd`. Combine with `|`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PrimeFlags(u32);
+
+impl PrimeFlags {
+    /// Sets close-on-exec on returned fd.
+    pub const CLOEXEC: PrimeFlags = PrimeFlags(ffi::drm::DRM_CLOEXEC as u32);
+    /// Allows mapping returned fd for writing.
+    pub const RDWR: PrimeFlags = PrimeFlags(ffi::drm::DRM_RDWR as u32);
+
+    /// No flags.
+    pub fn empty() -> Self {
+        PrimeFlags(0)
+    }
+
+    /// Get raw value of flags.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Check if all flags of `other` are set.
+    pub fn contains(&self, other: PrimeFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl ops::BitOr for PrimeFlags {
+    type Output = PrimeFlags;
+
+    fn bitor(self, other: PrimeFlags) -> PrimeFlags {
+        PrimeFlags(self.0 | other.0)
+    }
+}
+
+impl ops::BitOrAssign for PrimeFlags {
+    fn bitor_assign(&mut self, other: PrimeFlags) {
+        self.0 |= other.0;
+    }
+}
+
+/// PRIME support decoded from `Capability::Prime`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrimeCapabilities {
+    /// Buffers can be imported with `prime_fd_to_handle`.
+    pub import: bool,
+    /// Buffers can be exported with `prime_handle_to_fd`.
+    pub export: bool,
+}
+
+/// Type of DRM device node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeType {
+    /// Primary node, e.g. `/dev/dri/card0`. Allows mode setting.
+    Primary,
+    /// Control node. Not used by modern kernels.
+    Control,
+    /// Render node, e.g. `/dev/dri/renderD128`. Allows only rendering.
+    Render,
+}
+
 /// Get devices capability.
 ///
 /// Counterpart for `drmGetCap`.
@@ -44,3 +132,207 @@ pub fn get_cap(fd: io::RawFd, cap: Capability) -> Result<u64, i32> {
 
     if result == 0 { Ok(value) } else { Err(result) }
 }
+
+/// Enable or disable client capability.
+///
+/// Counterpart for `drmSetClientCap`.
+pub fn set_client_cap(fd: io::RawFd, cap: ClientCapability, value: u64) -> Result<(), i32> {
+    let result = unsafe { ffi::xf86drm::drmSetClientCap(fd, cap as u64, value) };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Become DRM master of the device.
+///
+/// Counterpart for `drmSetMaster`.
+pub fn set_master(fd: io::RawFd) -> Result<(), i32> {
+    let result = unsafe { ffi::xf86drm::drmSetMaster(fd) };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Stop being DRM master of the device.
+///
+/// Counterpart for `drmDropMaster`.
+pub fn drop_master(fd: io::RawFd) -> Result<(), i32> {
+    let result = unsafe { ffi::xf86drm::drmDropMaster(fd) };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Check if `fd` is DRM master of the device.
+///
+/// Authenticating magic `0` is never valid, but kernel checks master status first, so only
+/// non-masters get `EACCES`. This is the same probe as done by `drmIsMaster`.
+pub fn is_master(fd: io::RawFd) -> bool {
+    unsafe { ffi::xf86drm::drmAuthMagic(fd, 0) != -libc::EACCES }
+}
+
+/// Guard holding DRM master status. Master is dropped when guard goes out of scope.
+#[derive(Debug)]
+pub struct MasterGuard {
+    fd: io::RawFd,
+}
+
+impl MasterGuard {
+    /// Become DRM master of the device for lifetime of returned guard.
+    pub fn acquire(fd: io::RawFd) -> Result<Self, i32> {
+        set_master(fd)?;
+        Ok(MasterGuard { fd })
+    }
+}
+
+impl Drop for MasterGuard {
+    fn drop(&mut self) {
+        let _ = drop_master(self.fd);
+    }
+}
+
+/// Magic token used to authenticate client with DRM master.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AuthToken(ffi::drm::drm_magic_t);
+
+impl AuthToken {
+    /// Create token from raw value, e.g. one received from client.
+    pub fn from_raw(magic: u32) -> Self {
+        AuthToken(magic)
+    }
+
+    /// Get raw value of the token, e.g. to send it to the server.
+    pub fn as_raw(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Get magic token identifying this client.
+///
+/// Counterpart for `drmGetMagic`.
+pub fn get_magic(fd: io::RawFd) -> Result<AuthToken, i32> {
+    let mut magic = 0;
+    let result = unsafe { ffi::xf86drm::drmGetMagic(fd, &mut magic) };
+    if result == 0 { Ok(AuthToken(magic)) } else { Err(result) }
+}
+
+/// Authenticate client identified by `token`. Must be called by DRM master.
+///
+/// Counterpart for `drmAuthMagic`.
+pub fn auth_magic(fd: io::RawFd, token: AuthToken) -> Result<(), i32> {
+    let result = unsafe { ffi::xf86drm::drmAuthMagic(fd, token.0) };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Get version of the kernel driver.
+///
+/// Counterpart for `drmGetVersion`.
+pub fn get_version(fd: io::RawFd) -> Option<Version> {
+    unsafe { Version::from_raw(ffi::xf86drm::drmGetVersion(fd)) }
+}
+
+/// Get version of libdrm.
+///
+/// Counterpart for `drmGetLibVersion`.
+pub fn get_lib_version(fd: io::RawFd) -> Option<Version> {
+    unsafe { Version::from_raw(ffi::xf86drm::drmGetLibVersion(fd)) }
+}
+
+/// Get bus id of the device, e.g. "pci:0000:00:02.0".
+///
+/// Counterpart for `drmGetBusid`.
+pub fn get_busid(fd: io::RawFd) -> Option<String> {
+    let busid = unsafe { ffi::xf86drm::drmGetBusid(fd) };
+    if busid.is_null() {
+        return None;
+    }
+
+    let result = unsafe { CStr::from_ptr(busid) }.to_string_lossy().into_owned();
+    unsafe { ffi::xf86drm::drmFreeBusid(busid) };
+    Some(result)
+}
+
+/// Get type of device node `fd` was opened from.
+///
+/// Counterpart for `drmGetNodeTypeFromFd`.
+pub fn get_node_type(fd: io::RawFd) -> Option<NodeType> {
+    let node_type = unsafe { ffi::xf86drm::drmGetNodeTypeFromFd(fd) };
+    if node_type < 0 {
+        return None;
+    }
+
+    match node_type as u8 {
+        ffi::xf86drm::DRM_NODE_PRIMARY => Some(NodeType::Primary),
+        ffi::xf86drm::DRM_NODE_CONTROL => Some(NodeType::Control),
+        ffi::xf86drm::DRM_NODE_RENDER => Some(NodeType::Render),
+        _ => None,
+    }
+}
+
+/// Get path of device node `fd` was opened from.
+///
+/// Counterpart for `drmGetDeviceNameFromFd`.
+pub fn get_device_name(fd: io::RawFd) -> Option<PathBuf> {
+    unsafe { to_path(ffi::xf86drm::drmGetDeviceNameFromFd(fd)) }
+}
+
+/// Get path of primary node of the same device as `fd`.
+///
+/// Counterpart for `drmGetPrimaryDeviceNameFromFd`.
+pub fn get_primary_device_name(fd: io::RawFd) -> Option<PathBuf> {
+    unsafe { to_path(ffi::xf86drm::drmGetPrimaryDeviceNameFromFd(fd)) }
+}
+
+/// Get path of render node of the same device as `fd`.
+///
+/// Returns `None` if the device has no render node.
+///
+/// Counterpart for `drmGetRenderDeviceNameFromFd`.
+pub fn get_render_device_name(fd: io::RawFd) -> Option<PathBuf> {
+    unsafe { to_path(ffi::xf86drm::drmGetRenderDeviceNameFromFd(fd)) }
+}
+
+/// Copy path allocated by libdrm and free it.
+unsafe fn to_path(name: *mut libc::c_char) -> Option<PathBuf> {
+    if name.is_null() {
+        return None;
+    }
+
+    let path = PathBuf::from(OsStr::from_bytes(CStr::from_ptr(name).to_bytes()));
+    libc::free(name as *mut libc::c_void);
+    Some(path)
+}
+
+/// Get PRIME capabilities of the device.
+pub fn get_prime_capabilities(fd: io::RawFd) -> Result<PrimeCapabilities, i32> {
+    let value = get_cap(fd, Capability::Prime)?;
+    Ok(PrimeCapabilities {
+        import: value & ffi::drm::DRM_PRIME_CAP_IMPORT != 0,
+        export: value & ffi::drm::DRM_PRIME_CAP_EXPORT != 0,
+    })
+}
+
+/// Export buffer object as dma-buf fd which can be passed to other processes or devices.
+///
+/// Counterpart for `drmPrimeHandleToFD`.
+pub fn prime_handle_to_fd(handle: &GemHandle, flags: PrimeFlags) -> Result<io::OwnedFd, i32> {
+    let mut prime_fd = -1;
+    let result = unsafe {
+        ffi::xf86drm::drmPrimeHandleToFD(handle.get_fd(),
+                                         handle.get_handle(),
+                                         flags.bits(),
+                                         &mut prime_fd)
+    };
+    if result == 0 {
+        Ok(unsafe { io::OwnedFd::from_raw_fd(prime_fd) })
+    } else {
+        Err(result)
+    }
+}
+
+/// Import dma-buf fd as buffer object and return its handle. `prime_fd` is not consumed.
+///
+/// Importing dma-buf exported from the same device, or importing the same dma-buf twice, returns
+/// handle which may be already owned elsewhere. Wrap it with `GemHandle::from_raw` only if it is
+/// known to have no other owner.
+///
+/// Counterpart for `drmPrimeFDToHandle`.
+pub fn prime_fd_to_handle(fd: io::RawFd, prime_fd: io::RawFd) -> Result<u32, i32> {
+    let mut handle = 0;
+    let result = unsafe { ffi::xf86drm::drmPrimeFDToHandle(fd, prime_fd, &mut handle) };
+    if result == 0 { Ok(handle) } else { Err(result) }
+}
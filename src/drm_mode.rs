@@ -15,20 +15,65 @@
 // DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
+use std;
 use std::ffi::CString;
+use libc;
 use libc::c_char;
 use std::os::unix::io;
 
 use ffi;
-pub use resources::Resources;
+use drm;
+use gem::GemHandle;
+use atomic;
+pub use resources::{Resources, ResourcesDiff, ResourcesSnapshot, decode_mask};
 pub use crtc::{Crtc, CrtcId};
 pub use connector::{Connector, ConnectorId, Connection, ConnectorType, ConnectorTypeId};
 pub use encoder::{Encoder, EncoderId, EncoderType};
+pub use plane::{Plane, PlaneId};
 pub use mode_info::ModeInfo;
+pub use gamma::{GammaLut, NEUTRAL_TEMPERATURE};
+pub use color::{ColorLut, ColorLutEntry, ColorMatrix, get_degamma_lut_size, get_gamma_lut_size};
+pub use property::{Property, PropertyId, PropertyEnum, ObjectProperties, find_property};
+pub use atomic::{AtomicRequest, OutFence};
+pub use blob::{PropertyBlob, get_property_blob};
+pub use edid::{Edid, HdrStaticMetadata, get_edid};
+pub use hdr::{Chromaticity, Colorspace, Eotf, HdrOutputMetadata, get_colorspaces, add_colorspace,
+              add_hdr_output_metadata};
+pub use connector_property::{EnumProperty, ScalingMode, Underscan, BroadcastRgb, Dithering,
+                             ContentType, AspectRatio, get_connector_enum,
+                             get_connector_enum_values, set_connector_enum, add_connector_enum,
+                             get_underscan_borders, set_underscan_borders, get_max_bpc_range,
+                             get_max_bpc, set_max_bpc, add_max_bpc, set_dithering, add_dithering};
+pub use hdcp::{ContentProtection, ContentProtectionWatcher, HdcpContentType};
+pub use writeback::{attach_writeback, detach_writeback, enable_writeback_connectors,
+                    get_writeback_pixel_formats};
+pub use lease::{Lease, LesseeId, get_lease, is_non_desktop, list_lessees, revoke_lease};
+pub use snapshot::{KmsSnapshot, CrtcSnapshot, ModeSnapshot, PlaneSnapshot, PropertySnapshot};
+pub use planner::{ConnectorDescription, EncoderDescription, OutputAssignment, OutputRequest,
+                  ResourceDescription, plan_outputs};
+pub use layout::{Layout, LayoutPlan, Output, Rotation, ScreenLimits, Viewport};
+pub use dpms::{DpmsState, LinkStatus, add_active, add_link_status_good, get_dpms, get_link_status,
+               retrain_link, set_dpms};
+pub use vrr::{add_vrr_enabled, is_vrr_capable, is_vrr_enabled, set_vrr_enabled};
 
 pub const PAGE_FLIP_EVENT: u32 = 0x01;
 pub const PAGE_FLIP_ASYNC: u32 = 0x02;
 
+pub const ATOMIC_TEST_ONLY: u32 = ffi::drm_mode::DRM_MODE_ATOMIC_TEST_ONLY;
+pub const ATOMIC_NONBLOCK: u32 = ffi::drm_mode::DRM_MODE_ATOMIC_NONBLOCK;
+pub const ATOMIC_ALLOW_MODESET: u32 = ffi::drm_mode::DRM_MODE_ATOMIC_ALLOW_MODESET;
+
+pub const OBJECT_CRTC: u32 = ffi::drm_mode::DRM_MODE_OBJECT_CRTC;
+pub const OBJECT_CONNECTOR: u32 = ffi::drm_mode::DRM_MODE_OBJECT_CONNECTOR;
+pub const OBJECT_ENCODER: u32 = ffi::drm_mode::DRM_MODE_OBJECT_ENCODER;
+pub const OBJECT_PLANE: u32 = ffi::drm_mode::DRM_MODE_OBJECT_PLANE;
+pub const OBJECT_FB: u32 = ffi::drm_mode::DRM_MODE_OBJECT_FB;
+pub const OBJECT_BLOB: u32 = ffi::drm_mode::DRM_MODE_OBJECT_BLOB;
+pub const OBJECT_ANY: u32 = ffi::drm_mode::DRM_MODE_OBJECT_ANY;
+
+/// Cursor size assumed when device does not report `Capability::CursorWidth/CursorHeight`.
+const DEFAULT_CURSOR_SIZE: u64 = 64;
+
 /// Checks if mode setting for device describer by `busid` is supported.
 ///
 /// Counterpart for `drmCheckModesettingSupported`.
@@ -97,6 +142,38 @@ pub fn get_encoder(fd: io::RawFd, id: EncoderId) -> Option<Encoder> {
     }
 }
 
+/// Return vector of plane ids.
+///
+/// Counterpart for `drmModeGetPlaneResources`.
+pub fn get_plane_resources(fd: io::RawFd) -> Option<Vec<PlaneId>> {
+    let resources = unsafe { ffi::xf86drm_mode::drmModeGetPlaneResources(fd) };
+    if resources.is_null() {
+        return None;
+    }
+
+    let count = unsafe { (*resources).count_planes };
+    let mut vec = Vec::with_capacity(count as usize);
+    for pos in 0..count as isize {
+        vec.push(unsafe { *(*resources).planes.offset(pos) });
+    }
+    unsafe { ffi::xf86drm_mode::drmModeFreePlaneResources(resources) };
+    Some(vec)
+}
+
+/// Return Plane.
+///
+/// Allocated resources are automatically dropped.
+///
+/// Counterpart for `drmModeGetPlane`.
+pub fn get_plane(fd: io::RawFd, id: PlaneId) -> Option<Plane> {
+    let plane = unsafe { ffi::xf86drm_mode::drmModeGetPlane(fd, id) };
+    if plane.is_null() {
+        None
+    } else {
+        Some(Plane::new(plane))
+    }
+}
+
 /// Creates a new framebuffer with an buffer object as its scanout buffer.
 ///
 /// Returns newly created buffers ID on success or error code in case if failure.
@@ -152,6 +229,23 @@ pub fn set_crtc(fd: io::RawFd,
     if result == 0 { Ok(()) } else { Err(result) }
 }
 
+/// Disable CRTC and detach all connectors from it.
+///
+/// Counterpart of `drmModeSetCrtc` with no mode.
+pub fn disable_crtc(fd: io::RawFd, crtc_id: CrtcId) -> Result<(), i32> {
+    let result = unsafe {
+        ffi::xf86drm_mode::drmModeSetCrtc(fd,
+                                          crtc_id,
+                                          0,
+                                          0,
+                                          0,
+                                          std::ptr::null(),
+                                          0,
+                                          std::ptr::null_mut())
+    };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
 /// Perform page flip.
 ///
 /// Counterpart of `drmModePageFlip`. `drmModePageFlip` takes pointer to arbitrary data which is
@@ -169,3 +263,209 @@ pub fn page_flip(fd: io::RawFd,
     };
     if result == 0 { Ok(()) } else { Err(result) }
 }
+
+/// Set cursor image on CRTC or hide the cursor if `buffer` is `None`.
+///
+/// `width` and `height` must not exceed cursor size reported by device, otherwise `-EINVAL` is
+/// returned without calling the driver. `hotspot` is position of the pointer within the image;
+/// if given, it is passed to driver with `drmModeSetCursor2`, which virtual machine drivers need
+/// to draw the host cursor correctly.
+///
+/// Counterpart of `drmModeSetCursor` and `drmModeSetCursor2`.
+pub fn set_cursor(fd: io::RawFd,
+                  crtc_id: CrtcId,
+                  buffer: Option<&GemHandle>,
+                  width: u32,
+                  height: u32,
+                  hotspot: Option<(i32, i32)>)
+                  -> Result<(), i32> {
+    let buffer = match buffer {
+        Some(buffer) => buffer,
+        None => {
+            let result = unsafe { ffi::xf86drm_mode::drmModeSetCursor(fd, crtc_id, 0, 0, 0) };
+            return if result == 0 { Ok(()) } else { Err(result) };
+        }
+    };
+
+    let max_width = drm::get_cap(fd, drm::Capability::CursorWidth)
+        .unwrap_or(DEFAULT_CURSOR_SIZE);
+    let max_height = drm::get_cap(fd, drm::Capability::CursorHeight)
+        .unwrap_or(DEFAULT_CURSOR_SIZE);
+    if width == 0 || height == 0 || width as u64 > max_width || height as u64 > max_height {
+        return Err(-libc::EINVAL);
+    }
+
+    let result = unsafe {
+        match hotspot {
+            Some((hot_x, hot_y)) => {
+                ffi::xf86drm_mode::drmModeSetCursor2(fd,
+                                                     crtc_id,
+                                                     buffer.get_handle(),
+                                                     width,
+                                                     height,
+                                                     hot_x,
+                                                     hot_y)
+            }
+            None => {
+                ffi::xf86drm_mode::drmModeSetCursor(fd,
+                                                    crtc_id,
+                                                    buffer.get_handle(),
+                                                    width,
+                                                    height)
+            }
+        }
+    };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Move cursor on CRTC. Position is relative to top-left corner of the CRTC and may be negative.
+///
+/// Counterpart of `drmModeMoveCursor`.
+pub fn move_cursor(fd: io::RawFd, crtc_id: CrtcId, x: i32, y: i32) -> Result<(), i32> {
+    let result = unsafe { ffi::xf86drm_mode::drmModeMoveCursor(fd, crtc_id, x, y) };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Read gamma ramp of CRTC.
+///
+/// Returns `-ENOENT` if CRTC does not exist and `-EINVAL` if it does not support gamma ramp.
+///
+/// Counterpart of `drmModeCrtcGetGamma`.
+pub fn get_gamma(fd: io::RawFd, crtc_id: CrtcId) -> Result<GammaLut, i32> {
+    let size = get_crtc(fd, crtc_id).ok_or(-libc::ENOENT)?.get_gamma_size();
+    if size <= 0 {
+        return Err(-libc::EINVAL);
+    }
+
+    let mut lut = GammaLut::new(size as usize);
+    let result = unsafe {
+        ffi::xf86drm_mode::drmModeCrtcGetGamma(fd,
+                                               crtc_id,
+                                               size as u32,
+                                               lut.red.as_mut_ptr(),
+                                               lut.green.as_mut_ptr(),
+                                               lut.blue.as_mut_ptr())
+    };
+    if result == 0 { Ok(lut) } else { Err(result) }
+}
+
+/// Set gamma ramp of CRTC.
+///
+/// Size of `lut` must be equal to `Crtc::get_gamma_size`, otherwise kernel rejects it.
+///
+/// Counterpart of `drmModeCrtcSetGamma`.
+pub fn set_gamma(fd: io::RawFd, crtc_id: CrtcId, lut: &GammaLut) -> Result<(), i32> {
+    let size = match lut.size() {
+        Some(size) if size > 0 => size,
+        _ => return Err(-libc::EINVAL),
+    };
+
+    // libdrm takes mutable pointers but only reads from them.
+    let result = unsafe {
+        ffi::xf86drm_mode::drmModeCrtcSetGamma(fd,
+                                               crtc_id,
+                                               size as u32,
+                                               lut.red.as_ptr() as *mut u16,
+                                               lut.green.as_ptr() as *mut u16,
+                                               lut.blue.as_ptr() as *mut u16)
+    };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Return property.
+///
+/// Allocated resources are automatically dropped.
+///
+/// Counterpart for `drmModeGetProperty`.
+pub fn get_property(fd: io::RawFd, id: PropertyId) -> Option<Property> {
+    let property = unsafe { ffi::xf86drm_mode::drmModeGetProperty(fd, id) };
+    if property.is_null() {
+        None
+    } else {
+        Some(Property::new(property))
+    }
+}
+
+/// Return properties of mode object of given type, e.g. `OBJECT_CRTC`.
+///
+/// Atomic-only properties are reported only after enabling `drm::ClientCapability::Atomic`.
+///
+/// Allocated resources are automatically dropped.
+///
+/// Counterpart for `drmModeObjectGetProperties`.
+pub fn get_object_properties(fd: io::RawFd,
+                             object_id: u32,
+                             object_type: u32)
+                             -> Option<ObjectProperties> {
+    let properties = unsafe {
+        ffi::xf86drm_mode::drmModeObjectGetProperties(fd, object_id, object_type)
+    };
+    if properties.is_null() {
+        None
+    } else {
+        Some(ObjectProperties::new(properties))
+    }
+}
+
+/// Set property of mode object without atomic request.
+///
+/// Counterpart of `drmModeObjectSetProperty`.
+pub fn set_object_property(fd: io::RawFd,
+                           object_id: u32,
+                           object_type: u32,
+                           property_id: PropertyId,
+                           value: u64)
+                           -> Result<(), i32> {
+    let result = unsafe {
+        ffi::xf86drm_mode::drmModeObjectSetProperty(fd,
+                                                    object_id,
+                                                    object_type,
+                                                    property_id,
+                                                    value)
+    };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Apply all changes of atomic request.
+///
+/// `flags` is combination of `ATOMIC_*` flags and `PAGE_FLIP_EVENT`. As in `page_flip`,
+/// `user_data` is passed back in page flip events. Out-fences of previous commit of the request
+/// which were not taken are closed.
+///
+/// Counterpart of `drmModeAtomicCommit`.
+pub fn atomic_commit(fd: io::RawFd,
+                     request: &AtomicRequest,
+                     flags: u32,
+                     user_data: i32)
+                     -> Result<(), i32> {
+    atomic::rearm_out_fences(request);
+    let result = unsafe {
+        ffi::xf86drm_mode::drmModeAtomicCommit(fd,
+                                               request.as_ptr(),
+                                               flags,
+                                               user_data as *mut _)
+    };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
+
+/// Create property blob holding `data`. Returns id of the blob.
+///
+/// Counterpart of `drmModeCreatePropertyBlob`.
+pub fn create_property_blob(fd: io::RawFd, data: &[u8]) -> Result<u32, i32> {
+    let mut blob_id = 0;
+    let result = unsafe {
+        ffi::xf86drm_mode::drmModeCreatePropertyBlob(fd,
+                                                     data.as_ptr() as *const _,
+                                                     data.len(),
+                                                     &mut blob_id)
+    };
+    if result == 0 { Ok(blob_id) } else { Err(result) }
+}
+
+/// Destroy property blob.
+///
+/// Counterpart of `drmModeDestroyPropertyBlob`.
+pub fn destroy_property_blob(fd: io::RawFd, blob_id: u32) -> Result<(), i32> {
+    let result = unsafe { ffi::xf86drm_mode::drmModeDestroyPropertyBlob(fd, blob_id) };
+    if result == 0 { Ok(()) } else { Err(result) }
+}
@@ -0,0 +1,273 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Parsing of parts of EDID not interpreted by kernel.
+
+use std::os::unix::io;
+
+use blob;
+use connector::ConnectorId;
+use drm_mode;
+use property;
+
+/// Size of EDID block.
+const BLOCK_SIZE: usize = 128;
+
+/// Tag of CTA-861 extension block.
+const CTA_EXTENSION_TAG: u8 = 0x02;
+
+/// Tag of CTA-861 data block using extended tags.
+const CTA_EXTENDED_TAG: u8 = 7;
+
+/// Offset of first display descriptor in base block.
+const DESCRIPTORS_OFFSET: usize = 54;
+
+/// Size of display descriptor.
+const DESCRIPTOR_SIZE: usize = 18;
+
+/// Tag of display range limits descriptor.
+const RANGE_LIMITS_TAG: u8 = 0xfd;
+
+/// Extended tag of HDR static metadata data block.
+const CTA_HDR_STATIC_METADATA_TAG: u8 = 6;
+
+/// EDID of display connected to connector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edid {
+    data: Vec<u8>,
+}
+
+/// HDR capabilities of display, from HDR static metadata data block of CTA-861 extension.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HdrStaticMetadata {
+    /// Bitmask of supported EOTFs. Bit `n` is set if `Eotf` with value `n` is supported.
+    pub eotfs: u8,
+    /// Bitmask of supported static metadata descriptors. Bit 0 is static metadata type 1.
+    pub metadata_types: u8,
+    /// Desired content maximum luminance in cd/m².
+    pub max_luminance: Option<f64>,
+    /// Desired content maximum frame-average luminance in cd/m².
+    pub max_frame_average_luminance: Option<f64>,
+    /// Desired content minimum luminance in cd/m².
+    pub min_luminance: Option<f64>,
+}
+
+/// General methods
+impl Edid {
+    /// `Edid` constructor.
+    /// Does not check if passed data is valid.
+    pub fn new(data: Vec<u8>) -> Self {
+        Edid { data }
+    }
+
+    /// Get raw bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Iterate over payloads of CTA-861 data blocks of all CTA extension blocks. Each item is
+    /// pair of block tag and payload.
+    fn cta_data_blocks(&self) -> Vec<(u8, &[u8])> {
+        let mut blocks = Vec::new();
+        for extension in self.data.chunks(BLOCK_SIZE).skip(1) {
+            if extension.len() < BLOCK_SIZE || extension[0] != CTA_EXTENSION_TAG {
+                continue;
+            }
+
+            // Data block collection starts at byte 4 and ends where detailed timings start.
+            let end = (extension[2] as usize).min(BLOCK_SIZE - 1);
+            let mut offset = 4;
+            while offset < end {
+                let tag = extension[offset] >> 5;
+                let length = (extension[offset] & 0x1f) as usize;
+                if offset + 1 + length > end {
+                    break;
+                }
+                blocks.push((tag, &extension[offset + 1..offset + 1 + length]));
+                offset += 1 + length;
+            }
+        }
+        blocks
+    }
+
+    /// Get range of vertical refresh rates in Hz supported by display, as reported by display
+    /// range limits descriptor. Returns `None` if display does not report it.
+    pub fn vertical_refresh_range(&self) -> Option<(u32, u32)> {
+        if self.data.len() < BLOCK_SIZE {
+            return None;
+        }
+
+        let descriptor = self.data[DESCRIPTORS_OFFSET..BLOCK_SIZE - 2]
+            .chunks(DESCRIPTOR_SIZE)
+            .find(|d| d[0] == 0 && d[1] == 0 && d[2] == 0 && d[3] == RANGE_LIMITS_TAG)?;
+
+        // Since EDID 1.4 offset flags extend rates above 255 Hz.
+        let min_offset = if descriptor[4] & 0x03 == 0x03 { 255 } else { 0 };
+        let max_offset = if descriptor[4] & 0x02 == 0x02 { 255 } else { 0 };
+        Some((descriptor[5] as u32 + min_offset, descriptor[6] as u32 + max_offset))
+    }
+
+    /// Get HDR capabilities of the display. Returns `None` if display does not report them.
+    pub fn hdr_static_metadata(&self) -> Option<HdrStaticMetadata> {
+        let payload = self.cta_data_blocks()
+            .into_iter()
+            .filter(|&(tag, payload)| tag == CTA_EXTENDED_TAG && payload.len() >= 3)
+            .map(|(_, payload)| payload)
+            .find(|payload| payload[0] == CTA_HDR_STATIC_METADATA_TAG)?;
+
+        let luminance = |index: usize| -> Option<f64> {
+            payload.get(index).map(|&code| 50.0 * 2f64.powf(code as f64 / 32.0))
+        };
+        let max_luminance = luminance(3);
+        let min_luminance = match (max_luminance, payload.get(5)) {
+            (Some(max), Some(&code)) => Some(max * (code as f64 / 255.0).powi(2) / 100.0),
+            _ => None,
+        };
+
+        Some(HdrStaticMetadata {
+            eotfs: payload[1],
+            metadata_types: payload[2],
+            max_luminance,
+            max_frame_average_luminance: luminance(4),
+            min_luminance,
+        })
+    }
+}
+
+/// Read EDID of display connected to connector.
+///
+/// Returns `None` if nothing is connected or EDID could not be read.
+pub fn get_edid(fd: io::RawFd, connector_id: ConnectorId) -> Option<Edid> {
+    let (_, blob_id) =
+        property::find_property(fd, connector_id, drm_mode::OBJECT_CONNECTOR, "EDID")?;
+    if blob_id == 0 {
+        return None;
+    }
+    blob::get_property_blob(fd, blob_id as u32).map(Edid::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BLOCK_SIZE, Edid};
+
+    /// Base block of 24" 1920x1080 monitor with one extension block. Display range limits are
+    /// 56-76 Hz vertically.
+    const BASE_BLOCK: [u8; BLOCK_SIZE] = [
+        0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00,
+        0x10, 0xac, 0x7a, 0xa0, 0x4c, 0x4a, 0x33, 0x30,
+        0x1a, 0x1b, 0x01, 0x04, 0xa5, 0x34, 0x20, 0x78,
+        0x3a, 0xee, 0x95, 0xa3, 0x54, 0x4c, 0x99, 0x26,
+        0x0f, 0x50, 0x54, 0xa5, 0x4b, 0x00, 0x71, 0x4f,
+        0x81, 0x80, 0xa9, 0x40, 0xd1, 0xc0, 0x01, 0x01,
+        0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x02, 0x3a,
+        0x80, 0x18, 0x71, 0x38, 0x2d, 0x40, 0x58, 0x2c,
+        0x45, 0x00, 0x06, 0x44, 0x21, 0x00, 0x00, 0x1e,
+        0x00, 0x00, 0x00, 0xff, 0x00, 0x35, 0x4b, 0x39,
+        0x59, 0x44, 0x37, 0x41, 0x53, 0x30, 0x33, 0x4a,
+        0x4c, 0x0a, 0x00, 0x00, 0x00, 0xfc, 0x00, 0x44,
+        0x45, 0x4c, 0x4c, 0x20, 0x55, 0x32, 0x34, 0x31,
+        0x37, 0x48, 0x0a, 0x20, 0x00, 0x00, 0x00, 0xfd,
+        0x00, 0x38, 0x4c, 0x1e, 0x53, 0x11, 0x00, 0x0a,
+        0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x01, 0xe8,
+    ];
+
+    /// Build EDID with CTA-861 extension holding given data block collection.
+    fn with_cta_blocks(data_blocks: &[u8]) -> Edid {
+        let mut extension = vec![0; BLOCK_SIZE];
+        extension[0] = 0x02;
+        extension[1] = 0x03;
+        extension[2] = (4 + data_blocks.len()) as u8;
+        extension[4..4 + data_blocks.len()].copy_from_slice(data_blocks);
+        let sum = extension.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        extension[BLOCK_SIZE - 1] = 0u8.wrapping_sub(sum);
+
+        let mut data = BASE_BLOCK.to_vec();
+        data.extend(extension);
+        Edid::new(data)
+    }
+
+    /// Offset of display range limits descriptor in `BASE_BLOCK`.
+    const RANGE_LIMITS: usize = 108;
+
+    #[test]
+    fn parses_vertical_refresh_range() {
+        assert_eq!(Edid::new(BASE_BLOCK.to_vec()).vertical_refresh_range(), Some((56, 76)));
+        assert_eq!(with_cta_blocks(&[]).vertical_refresh_range(), Some((56, 76)));
+    }
+
+    #[test]
+    fn applies_refresh_rate_offsets() {
+        let mut data = BASE_BLOCK.to_vec();
+        data[RANGE_LIMITS + 4] = 0x02;
+        data[RANGE_LIMITS + 6] = 0x2d;
+        assert_eq!(Edid::new(data.clone()).vertical_refresh_range(), Some((56, 300)));
+
+        data[RANGE_LIMITS + 4] = 0x03;
+        data[RANGE_LIMITS + 5] = 0x05;
+        assert_eq!(Edid::new(data.clone()).vertical_refresh_range(), Some((260, 300)));
+
+        // Horizontal offsets do not affect vertical range.
+        data[RANGE_LIMITS + 4] = 0x0c;
+        assert_eq!(Edid::new(data).vertical_refresh_range(), Some((5, 45)));
+    }
+
+    #[test]
+    fn missing_vertical_refresh_range() {
+        let mut data = BASE_BLOCK.to_vec();
+        data[RANGE_LIMITS + 3] = 0xfe;
+        assert_eq!(Edid::new(data).vertical_refresh_range(), None);
+        assert_eq!(Edid::new(BASE_BLOCK[..100].to_vec()).vertical_refresh_range(), None);
+    }
+
+    #[test]
+    fn parses_hdr_static_metadata() {
+        // Video data block followed by HDR static metadata block with SDR and PQ EOTFs.
+        let edid = with_cta_blocks(&[0x42, 0x10, 0x04, 0xe6, 0x06, 0x05, 0x01, 0x60, 0x50, 0x30]);
+        let metadata = edid.hdr_static_metadata().unwrap();
+        assert_eq!(metadata.eotfs, 0x05);
+        assert_eq!(metadata.metadata_types, 0x01);
+        assert_eq!(metadata.max_luminance, Some(400.0));
+        let max_fall = metadata.max_frame_average_luminance.unwrap();
+        assert!((max_fall - 50.0 * 2f64.powf(2.5)).abs() < 1e-9);
+        let min = metadata.min_luminance.unwrap();
+        assert!((min - 400.0 * (48.0f64 / 255.0).powi(2) / 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hdr_luminance_is_optional() {
+        let edid = with_cta_blocks(&[0xe3, 0x06, 0x0d, 0x01]);
+        let metadata = edid.hdr_static_metadata().unwrap();
+        assert_eq!(metadata.eotfs, 0x0d);
+        assert_eq!(metadata.max_luminance, None);
+        assert_eq!(metadata.max_frame_average_luminance, None);
+        assert_eq!(metadata.min_luminance, None);
+
+        // Minimum luminance is relative to maximum, so it cannot be decoded without it.
+        let edid = with_cta_blocks(&[0xe4, 0x06, 0x05, 0x01, 0x60]);
+        let metadata = edid.hdr_static_metadata().unwrap();
+        assert_eq!(metadata.max_luminance, Some(400.0));
+        assert_eq!(metadata.min_luminance, None);
+    }
+
+    #[test]
+    fn missing_hdr_static_metadata() {
+        assert_eq!(Edid::new(BASE_BLOCK.to_vec()).hdr_static_metadata(), None);
+        assert_eq!(with_cta_blocks(&[0x42, 0x10, 0x04]).hdr_static_metadata(), None);
+        // Truncated block claiming more bytes than the collection holds.
+        assert_eq!(with_cta_blocks(&[0xe6, 0x06, 0x05]).hdr_static_metadata(), None);
+    }
+}
@@ -19,6 +19,7 @@ use std;
 
 use ffi;
 use crtc;
+use resources::Resources;
 
 /// Type of encoder id.
 pub type EncoderId = u32;
@@ -38,6 +39,16 @@ impl Encoder {
     pub fn new(encoder: ffi::xf86drm_mode::drmModeEncoderPtr) -> Self {
         Encoder { encoder: encoder }
     }
+
+    /// Get ids of CRTCs the encoder can drive.
+    pub fn get_possible_crtc_ids(&self, resources: &Resources) -> Vec<crtc::CrtcId> {
+        resources.get_crtcs_from_mask(self.get_possible_crtcs())
+    }
+
+    /// Get ids of encoders which can drive the same CRTC together with this encoder.
+    pub fn get_possible_clone_ids(&self, resources: &Resources) -> Vec<EncoderId> {
+        resources.get_encoders_from_mask(self.get_possible_clones())
+    }
 }
 
 /// Getters for original members
@@ -0,0 +1,518 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Reading and decoding of `drm_event` records from DRM fd.
+//!
+//! Unlike `handle_event`, which calls `drmHandleEvent` to dispatch events to callbacks, this
+//! module does not use libdrm at all. It returns decoded events as values, so event loops can
+//! read the fd themselves once it becomes readable.
+
+use libc;
+use std::io;
+use std::os::unix::io::RawFd;
+use byteorder::{ByteOrder, NativeEndian};
+
+use ffi;
+
+/// Size of buffer used for reading events. Same as the one used by `drmHandleEvent`.
+const BUFFER_SIZE: usize = 1024;
+
+/// Size of `drm_event` header.
+const HEADER_SIZE: usize = 8;
+
+/// Size of `drm_event_vblank`.
+const VBLANK_SIZE: usize = 32;
+
+/// Data carried by v-blank and page flip events.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VBlankEvent {
+    pub sequence: u32,
+    pub sec: u32,
+    pub usec: u32,
+    /// Value passed as `user_data` to `page_flip`.
+    pub user_data: i32,
+    /// Id of CRTC the event was generated for. Zero on kernels not reporting it.
+    pub crtc_id: u32,
+}
+
+/// Event read from DRM device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    VBlank(VBlankEvent),
+    PageFlip(VBlankEvent),
+    /// Event of type not known to this crate. Carries type from event header.
+    Unknown(u32),
+}
+
+/// Decode events from buffer filled by reading DRM device.
+///
+/// Kernel always returns whole events, so truncated record at the end of the buffer is ignored.
+pub fn parse_events(buffer: &[u8]) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut offset = 0;
+    while buffer.len() - offset >= HEADER_SIZE {
+        let event_type = NativeEndian::read_u32(&buffer[offset..offset + 4]);
+        let length = NativeEndian::read_u32(&buffer[offset + 4..offset + 8]) as usize;
+        if length < HEADER_SIZE || offset + length > buffer.len() {
+            break;
+        }
+
+        let data = &buffer[offset..offset + length];
+        events.push(match event_type as libc::c_int {
+            ffi::drm::DRM_EVENT_VBLANK if length >= VBLANK_SIZE => {
+                Event::VBlank(parse_vblank(data))
+            }
+            ffi::drm::DRM_EVENT_FLIP_COMPLETE if length >= VBLANK_SIZE => {
+                Event::PageFlip(parse_vblank(data))
+            }
+            _ => Event::Unknown(event_type),
+        });
+        offset += length;
+    }
+    events
+}
+
+/// Decode `drm_event_vblank`.
+fn parse_vblank(data: &[u8]) -> VBlankEvent {
+    VBlankEvent {
+        user_data: NativeEndian::read_u64(&data[8..16]) as i32,
+        sec: NativeEndian::read_u32(&data[16..20]),
+        usec: NativeEndian::read_u32(&data[20..24]),
+        sequence: NativeEndian::read_u32(&data[24..28]),
+        crtc_id: NativeEndian::read_u32(&data[28..32]),
+    }
+}
+
+/// Read once from `fd` into `buffer`, retrying on `EINTR`.
+pub fn read(fd: RawFd, buffer: &mut [u8]) -> io::Result<usize> {
+    loop {
+        let result = unsafe {
+            libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len())
+        };
+        if result >= 0 {
+            return Ok(result as usize);
+        }
+
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+/// Read pending events from DRM device.
+///
+/// Blocks if `fd` is in blocking mode and there are no events. In non-blocking mode returns error
+/// of kind `WouldBlock` instead.
+pub fn read_events(fd: RawFd) -> io::Result<Vec<Event>> {
+    let mut buffer = [0; BUFFER_SIZE];
+    let length = read(fd, &mut buffer)?;
+    Ok(parse_events(&buffer[..length]))
+}
+
+/// Switch `fd` to non-blocking mode.
+pub fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if flags & libc::O_NONBLOCK == 0 &&
+       unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+pub use self::stream::EventStream;
+
+#[cfg(feature = "calloop")]
+pub use self::source::EventSource;
+
+#[cfg(feature = "tokio")]
+mod stream {
+    use std::collections::VecDeque;
+    use std::io;
+    use std::os::unix::io::RawFd;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures_core::Stream;
+    use tokio::io::unix::AsyncFd;
+
+    use super::{Event, BUFFER_SIZE};
+
+    /// Stream of events read from DRM device.
+    ///
+    /// Must be created from within tokio runtime. The fd is switched to non-blocking mode and is
+    /// not closed when stream is dropped.
+    pub struct EventStream {
+        fd: AsyncFd<RawFd>,
+        pending: VecDeque<Event>,
+    }
+
+    impl EventStream {
+        /// `EventStream` constructor.
+        pub fn new(fd: RawFd) -> io::Result<Self> {
+            super::set_nonblocking(fd)?;
+            Ok(EventStream {
+                fd: AsyncFd::new(fd)?,
+                pending: VecDeque::new(),
+            })
+        }
+    }
+
+    impl Stream for EventStream {
+        type Item = io::Result<Event>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+            let this = self.get_mut();
+            loop {
+                if let Some(event) = this.pending.pop_front() {
+                    return Poll::Ready(Some(Ok(event)));
+                }
+
+                let mut guard = match this.fd.poll_read_ready(cx) {
+                    Poll::Ready(Ok(guard)) => guard,
+                    Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(error))),
+                    Poll::Pending => return Poll::Pending,
+                };
+
+                let mut buffer = [0; BUFFER_SIZE];
+                match guard.try_io(|fd| super::read(*fd.get_ref(), &mut buffer)) {
+                    Ok(Ok(0)) => return Poll::Ready(None),
+                    Ok(Ok(length)) => this.pending.extend(super::parse_events(&buffer[..length])),
+                    Ok(Err(error)) => return Poll::Ready(Some(Err(error))),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "calloop")]
+mod source {
+    use std::io;
+    use std::os::unix::io::{AsFd, BorrowedFd, RawFd};
+
+    use calloop;
+    use calloop::generic::Generic;
+    use calloop::{Interest, Mode, Poll, PostAction, Readiness, Token, TokenFactory};
+
+    use super::{Event, BUFFER_SIZE};
+
+    /// Raw fd borrowed for the lifetime of event source.
+    struct DeviceFd(RawFd);
+
+    impl AsFd for DeviceFd {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            unsafe { BorrowedFd::borrow_raw(self.0) }
+        }
+    }
+
+    /// `calloop` event source reading events from DRM device.
+    ///
+    /// The fd is switched to non-blocking mode and must stay open as long as the source is
+    /// registered in event loop.
+    pub struct EventSource {
+        source: Generic<DeviceFd>,
+    }
+
+    impl EventSource {
+        /// `EventSource` constructor.
+        pub fn new(fd: RawFd) -> io::Result<Self> {
+            super::set_nonblocking(fd)?;
+            Ok(EventSource { source: Generic::new(DeviceFd(fd), Interest::READ, Mode::Level) })
+        }
+    }
+
+    impl calloop::EventSource for EventSource {
+        type Event = Event;
+        type Metadata = ();
+        type Ret = ();
+        type Error = io::Error;
+
+        fn process_events<F>(&mut self,
+                             readiness: Readiness,
+                             token: Token,
+                             mut callback: F)
+                             -> io::Result<PostAction>
+            where F: FnMut(Event, &mut ())
+        {
+            self.source.process_events(readiness, token, |_, fd| {
+                let mut buffer = [0; BUFFER_SIZE];
+                loop {
+                    match super::read(fd.as_ref().0, &mut buffer) {
+                        Ok(0) => return Ok(PostAction::Remove),
+                        Ok(length) => {
+                            for event in super::parse_events(&buffer[..length]) {
+                                callback(event, &mut ());
+                            }
+                        }
+                        Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => {
+                            return Ok(PostAction::Continue)
+                        }
+                        Err(error) => return Err(error),
+                    }
+                }
+            })
+        }
+
+        fn register(&mut self,
+                    poll: &mut Poll,
+                    token_factory: &mut TokenFactory)
+                    -> calloop::Result<()> {
+            self.source.register(poll, token_factory)
+        }
+
+        fn reregister(&mut self,
+                      poll: &mut Poll,
+                      token_factory: &mut TokenFactory)
+                      -> calloop::Result<()> {
+            self.source.reregister(poll, token_factory)
+        }
+
+        fn unregister(&mut self, poll: &mut Poll) -> calloop::Result<()> {
+            self.source.unregister(poll)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{ByteOrder, NativeEndian};
+    #[cfg(any(feature = "tokio", feature = "calloop"))]
+    use libc;
+    #[cfg(any(feature = "tokio", feature = "calloop"))]
+    use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+
+    use super::{parse_events, Event, VBlankEvent, VBLANK_SIZE};
+
+    /// Encode `drm_event_vblank` of given type.
+    fn vblank(event_type: u32, event: &VBlankEvent) -> Vec<u8> {
+        let mut data = vec![0; VBLANK_SIZE];
+        NativeEndian::write_u32(&mut data[0..4], event_type);
+        NativeEndian::write_u32(&mut data[4..8], VBLANK_SIZE as u32);
+        NativeEndian::write_u64(&mut data[8..16], event.user_data as u64);
+        NativeEndian::write_u32(&mut data[16..20], event.sec);
+        NativeEndian::write_u32(&mut data[20..24], event.usec);
+        NativeEndian::write_u32(&mut data[24..28], event.sequence);
+        NativeEndian::write_u32(&mut data[28..32], event.crtc_id);
+        data
+    }
+
+    fn sample(sequence: u32) -> VBlankEvent {
+        VBlankEvent {
+            sequence,
+            sec: 1000,
+            usec: 500,
+            user_data: 42,
+            crtc_id: 31,
+        }
+    }
+
+    #[test]
+    fn parses_vblank_and_flip_complete() {
+        let mut buffer = vblank(0x01, &sample(1));
+        buffer.extend(vblank(0x02, &sample(2)));
+        assert_eq!(parse_events(&buffer),
+                   vec![Event::VBlank(sample(1)), Event::PageFlip(sample(2))]);
+    }
+
+    #[test]
+    fn reports_unknown_events() {
+        let mut buffer = vec![0; 12];
+        NativeEndian::write_u32(&mut buffer[0..4], 0x80000000);
+        NativeEndian::write_u32(&mut buffer[4..8], 12);
+        buffer.extend(vblank(0x01, &sample(3)));
+        assert_eq!(parse_events(&buffer),
+                   vec![Event::Unknown(0x80000000), Event::VBlank(sample(3))]);
+    }
+
+    #[test]
+    fn ignores_truncated_event() {
+        let mut buffer = vblank(0x01, &sample(1));
+        let second = vblank(0x02, &sample(2));
+        buffer.extend(&second[..20]);
+        assert_eq!(parse_events(&buffer), vec![Event::VBlank(sample(1))]);
+        assert_eq!(parse_events(&second[..4]), vec![]);
+    }
+
+    #[test]
+    fn stops_at_invalid_length() {
+        let mut buffer = vblank(0x01, &sample(1));
+        NativeEndian::write_u32(&mut buffer[4..8], 4);
+        assert_eq!(parse_events(&buffer), vec![]);
+    }
+
+    #[test]
+    fn short_vblank_is_unknown() {
+        let mut buffer = vblank(0x01, &sample(1));
+        buffer.truncate(24);
+        NativeEndian::write_u32(&mut buffer[4..8], 24);
+        assert_eq!(parse_events(&buffer), vec![Event::Unknown(0x01)]);
+    }
+
+    /// Create non-blocking pipe standing in for device fd. Returns read and write end.
+    #[cfg(any(feature = "tokio", feature = "calloop"))]
+    fn pipe() -> (OwnedFd, OwnedFd) {
+        let mut fds = [0; 2];
+        let flags = libc::O_NONBLOCK | libc::O_CLOEXEC;
+        assert_eq!(unsafe { libc::pipe2(fds.as_mut_ptr(), flags) }, 0);
+        unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) }
+    }
+
+    #[cfg(any(feature = "tokio", feature = "calloop"))]
+    fn write_all(fd: &OwnedFd, data: &[u8]) {
+        let written = unsafe {
+            libc::write(fd.as_raw_fd(), data.as_ptr() as *const libc::c_void, data.len())
+        };
+        assert_eq!(written, data.len() as isize);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn stream_reads_events_until_eof() {
+        use futures_core::Stream;
+        use std::future;
+        use std::pin::Pin;
+        use std::task::Poll;
+        use tokio::runtime::Builder;
+
+        use super::EventStream;
+
+        let (read, write) = pipe();
+        let runtime = Builder::new_current_thread().enable_io().build().unwrap();
+        let _context = runtime.enter();
+        let mut stream = EventStream::new(read.as_raw_fd()).unwrap();
+        let mut next = |wait: bool| {
+            runtime.block_on(future::poll_fn(|cx| {
+                match Pin::new(&mut stream).poll_next(cx) {
+                    Poll::Ready(item) => Poll::Ready(Some(item.map(|result| result.unwrap()))),
+                    Poll::Pending if !wait => Poll::Ready(None),
+                    Poll::Pending => Poll::Pending,
+                }
+            }))
+        };
+
+        let mut buffer = vblank(0x01, &sample(1));
+        buffer.extend(vblank(0x02, &sample(2)));
+        buffer.extend(vblank(0x01, &sample(3)));
+        write_all(&write, &buffer);
+        assert_eq!(next(true), Some(Some(Event::VBlank(sample(1)))));
+        assert_eq!(next(true), Some(Some(Event::PageFlip(sample(2)))));
+        assert_eq!(next(true), Some(Some(Event::VBlank(sample(3)))));
+
+        // Pipe is drained, so readiness is cleared and stream waits for more data.
+        assert_eq!(next(false), None);
+        write_all(&write, &vblank(0x02, &sample(4)));
+        assert_eq!(next(true), Some(Some(Event::PageFlip(sample(4)))));
+
+        drop(write);
+        assert_eq!(next(true), Some(None));
+    }
+
+    /// Event source recording results of processing.
+    #[cfg(feature = "calloop")]
+    struct Recorder {
+        source: super::EventSource,
+        actions: ::std::rc::Rc<::std::cell::RefCell<Vec<calloop::PostAction>>>,
+    }
+
+    #[cfg(feature = "calloop")]
+    impl calloop::EventSource for Recorder {
+        type Event = Event;
+        type Metadata = ();
+        type Ret = ();
+        type Error = ::std::io::Error;
+
+        fn process_events<F>(&mut self,
+                             readiness: calloop::Readiness,
+                             token: calloop::Token,
+                             callback: F)
+                             -> ::std::io::Result<calloop::PostAction>
+            where F: FnMut(Event, &mut ())
+        {
+            let action = self.source.process_events(readiness, token, callback)?;
+            self.actions.borrow_mut().push(action);
+            Ok(action)
+        }
+
+        fn register(&mut self,
+                    poll: &mut calloop::Poll,
+                    token_factory: &mut calloop::TokenFactory)
+                    -> calloop::Result<()> {
+            self.source.register(poll, token_factory)
+        }
+
+        fn reregister(&mut self,
+                      poll: &mut calloop::Poll,
+                      token_factory: &mut calloop::TokenFactory)
+                      -> calloop::Result<()> {
+            self.source.reregister(poll, token_factory)
+        }
+
+        fn unregister(&mut self, poll: &mut calloop::Poll) -> calloop::Result<()> {
+            self.source.unregister(poll)
+        }
+    }
+
+    #[cfg(feature = "calloop")]
+    #[test]
+    fn source_dispatches_events_until_eof() {
+        use calloop::{EventLoop, PostAction};
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use std::time::Duration;
+
+        use super::EventSource;
+
+        let (read, write) = pipe();
+        let actions = Rc::new(RefCell::new(Vec::new()));
+        let recorder = Recorder {
+            source: EventSource::new(read.as_raw_fd()).unwrap(),
+            actions: actions.clone(),
+        };
+        let mut event_loop: EventLoop<Vec<Event>> = EventLoop::try_new().unwrap();
+        event_loop.handle()
+            .insert_source(recorder, |event, _, events| events.push(event))
+            .unwrap();
+
+        let mut events = Vec::new();
+        let mut buffer = vblank(0x01, &sample(1));
+        buffer.extend(vblank(0x02, &sample(2)));
+        write_all(&write, &buffer);
+        event_loop.dispatch(Some(Duration::from_secs(1)), &mut events).unwrap();
+        assert_eq!(events, vec![Event::VBlank(sample(1)), Event::PageFlip(sample(2))]);
+        assert_eq!(*actions.borrow(), vec![PostAction::Continue]);
+
+        // Source drained the pipe, so level-triggered loop does not report it again.
+        event_loop.dispatch(Some(Duration::ZERO), &mut events).unwrap();
+        assert_eq!(actions.borrow().len(), 1);
+
+        write_all(&write, &vblank(0x01, &sample(3)));
+        drop(write);
+        event_loop.dispatch(Some(Duration::from_secs(1)), &mut events).unwrap();
+        assert_eq!(events.last(), Some(&Event::VBlank(sample(3))));
+        assert_eq!(*actions.borrow(), vec![PostAction::Continue, PostAction::Remove]);
+
+        // Removed source is not polled again, although closed pipe stays readable.
+        event_loop.dispatch(Some(Duration::ZERO), &mut events).unwrap();
+        assert_eq!(actions.borrow().len(), 2);
+        assert_eq!(events.len(), 3);
+    }
+}
@@ -796,20 +796,24 @@ impl ::std::default::Default for drm_set_version {
 
 
 /** DRM_IOCTL_GEM_CLOSE ioctl argument type */
-struct drm_gem_close {
+#[repr(C)]
+pub struct drm_gem_close {
 	/** Handle of the object to be closed. */
-	handle: u32,
-	pad: u32
+	pub handle: u32,
+	pub pad: u32
+}
+impl ::std::default::Default for drm_gem_close {
+    fn default() -> drm_gem_close { unsafe { ::std::mem::zeroed() } }
 }
 
 /** DRM_IOCTL_GEM_FLINK ioctl argument type */
 #[repr(C)]
 pub struct drm_gem_flink {
 	/** Handle for the object being named */
-	handle: u32,
+	pub handle: u32,
 
 	/** Returned global name */
-	name: u32
+	pub name: u32
 }
 impl ::std::default::Default for drm_gem_flink {
     fn default() -> drm_gem_flink { unsafe { ::std::mem::zeroed() } }
@@ -820,13 +824,13 @@ impl ::std::default::Default for drm_gem_flink {
 #[repr(C)]
 pub struct drm_gem_open {
 	/** Name of object being opened */
-	name: u32,
+	pub name: u32,
 
 	/** Returned handle for the object */
-	handle: u32,
+	pub handle: u32,
 
 	/** Returned size of the object */
-	size: u64
+	pub size: u64
 }
 impl ::std::default::Default for drm_gem_open {
     fn default() -> drm_gem_open { unsafe { ::std::mem::zeroed() } }
@@ -890,6 +894,22 @@ pub const DRM_CLIENT_CAP_UNIVERSAL_PLANES: c_int = 2;
  */
 pub const DRM_CLIENT_CAP_ATOMIC: c_int = 3;
 
+/**
+ * DRM_CLIENT_CAP_ASPECT_RATIO
+ *
+ * If set to 1, the DRM core will provide aspect ratio information in modes.
+ */
+pub const DRM_CLIENT_CAP_ASPECT_RATIO: c_int = 4;
+
+/**
+ * DRM_CLIENT_CAP_WRITEBACK_CONNECTORS
+ *
+ * If set to 1, the DRM core will expose special connectors to be used for
+ * writing back to memory the scene setup in the commit. Depends on client
+ * also supporting DRM_CLIENT_CAP_ATOMIC
+ */
+pub const DRM_CLIENT_CAP_WRITEBACK_CONNECTORS: c_int = 5;
+
 /** DRM_IOCTL_SET_CLIENT_CAP ioctl argument type */
 #[repr(C)]
 pub struct drm_set_client_cap {
@@ -902,7 +922,7 @@ impl ::std::default::Default for drm_set_client_cap {
 
 
 pub const DRM_RDWR: c_int = O_RDWR;
-// const DRM_CLOEXEC: c_int = O_CLOEXEC;
+pub const DRM_CLOEXEC: c_int = O_CLOEXEC;
 #[repr(C)]
 pub struct drm_prime_handle {
 	handle: u32,
@@ -920,6 +940,46 @@ impl ::std::default::Default for drm_prime_handle {
 
 // #include "drm_mode.h"
 
+/* Encoding of ioctl numbers, see <asm-generic/ioctl.h> */
+const _IOC_NRSHIFT: c_ulong = 0;
+const _IOC_TYPESHIFT: c_ulong = 8;
+const _IOC_SIZESHIFT: c_ulong = 16;
+const _IOC_DIRSHIFT: c_ulong = 30;
+
+const _IOC_NONE: c_ulong = 0;
+const _IOC_WRITE: c_ulong = 1;
+const _IOC_READ: c_ulong = 2;
+
+const fn _ioc(dir: c_ulong, nr: c_ulong, size: usize) -> c_ulong {
+    (dir << _IOC_DIRSHIFT) | (DRM_IOCTL_BASE << _IOC_TYPESHIFT) | (nr << _IOC_NRSHIFT) |
+    ((size as c_ulong) << _IOC_SIZESHIFT)
+}
+
+pub const DRM_IOCTL_BASE: c_ulong = b'd' as c_ulong;
+
+pub const fn drm_io(nr: c_ulong) -> c_ulong { _ioc(_IOC_NONE, nr, 0) }
+pub const fn drm_ior(nr: c_ulong, size: usize) -> c_ulong { _ioc(_IOC_READ, nr, size) }
+pub const fn drm_iow(nr: c_ulong, size: usize) -> c_ulong { _ioc(_IOC_WRITE, nr, size) }
+pub const fn drm_iowr(nr: c_ulong, size: usize) -> c_ulong { _ioc(_IOC_READ | _IOC_WRITE, nr, size) }
+
+pub const DRM_IOCTL_GEM_CLOSE: c_ulong = drm_iow(0x09, ::std::mem::size_of::<drm_gem_close>());
+pub const DRM_IOCTL_GEM_FLINK: c_ulong = drm_iowr(0x0a, ::std::mem::size_of::<drm_gem_flink>());
+pub const DRM_IOCTL_GEM_OPEN: c_ulong = drm_iowr(0x0b, ::std::mem::size_of::<drm_gem_open>());
+
+pub const DRM_IOCTL_MODE_CREATE_DUMB: c_ulong =
+    drm_iowr(0xb2, ::std::mem::size_of::<::ffi::drm_mode::drm_mode_create_dumb>());
+pub const DRM_IOCTL_MODE_MAP_DUMB: c_ulong =
+    drm_iowr(0xb3, ::std::mem::size_of::<::ffi::drm_mode::drm_mode_map_dumb>());
+
+pub const DRM_IOCTL_MODE_CREATE_LEASE: c_ulong =
+    drm_iowr(0xc6, ::std::mem::size_of::<::ffi::drm_mode::drm_mode_create_lease>());
+pub const DRM_IOCTL_MODE_LIST_LESSEES: c_ulong =
+    drm_iowr(0xc7, ::std::mem::size_of::<::ffi::drm_mode::drm_mode_list_lessees>());
+pub const DRM_IOCTL_MODE_GET_LEASE: c_ulong =
+    drm_iowr(0xc8, ::std::mem::size_of::<::ffi::drm_mode::drm_mode_get_lease>());
+pub const DRM_IOCTL_MODE_REVOKE_LEASE: c_ulong =
+    drm_iowr(0xc9, ::std::mem::size_of::<::ffi::drm_mode::drm_mode_revoke_lease>());
+
 /**
  * Device specific ioctls should only be in their respective headers
  * The device specific ioctl range is from 0x40 to 0x9f.
@@ -78,6 +78,10 @@ pub const DRM_MODE_DPMS_STANDBY: c_int = 1;
 pub const DRM_MODE_DPMS_SUSPEND: c_int = 2;
 pub const DRM_MODE_DPMS_OFF: c_int = 3;
 
+/* Link Status options */
+pub const DRM_MODE_LINK_STATUS_GOOD: c_int = 0;
+pub const DRM_MODE_LINK_STATUS_BAD: c_int = 1;
+
 /* Scaling mode options */
 pub const DRM_MODE_SCALE_NONE: c_int = 0; /* Unmodified timing (display or
 					     software can still scale) */
@@ -102,8 +106,8 @@ pub const DRM_MODE_DIRTY_ANNOTATE: c_int = 2;
 
 #[repr(C)]
 pub struct drm_mode_property_enum {
-	value: u64,
-	name: [c_char; DRM_PROP_NAME_LEN as usize]
+	pub value: u64,
+	pub name: [c_char; DRM_PROP_NAME_LEN as usize]
 }
 impl ::std::default::Default for drm_mode_property_enum {
     fn default() -> drm_mode_property_enum { unsafe { ::std::mem::zeroed() } }
@@ -149,3 +153,128 @@ impl ::std::default::Default for drm_mode_destroy_dumb {
     fn default() -> drm_mode_destroy_dumb { unsafe { ::std::mem::zeroed() } }
 }
 
+
+pub const DRM_MODE_OBJECT_CRTC: u32 = 0xcccccccc;
+pub const DRM_MODE_OBJECT_CONNECTOR: u32 = 0xc0c0c0c0;
+pub const DRM_MODE_OBJECT_ENCODER: u32 = 0xe0e0e0e0;
+pub const DRM_MODE_OBJECT_MODE: u32 = 0xdededede;
+pub const DRM_MODE_OBJECT_PROPERTY: u32 = 0xb0b0b0b0;
+pub const DRM_MODE_OBJECT_FB: u32 = 0xfbfbfbfb;
+pub const DRM_MODE_OBJECT_BLOB: u32 = 0xbbbbbbbb;
+pub const DRM_MODE_OBJECT_PLANE: u32 = 0xeeeeeeee;
+pub const DRM_MODE_OBJECT_ANY: u32 = 0;
+
+#[repr(C)]
+pub struct drm_color_ctm {
+    /*
+     * Conversion matrix in S31.32 sign-magnitude
+     * (not two's complement!) format.
+     */
+    pub matrix: [u64; 9]
+}
+impl ::std::default::Default for drm_color_ctm {
+    fn default() -> drm_color_ctm { unsafe { ::std::mem::zeroed() } }
+}
+
+#[repr(C)]
+pub struct drm_color_lut {
+    /*
+     * Data is U0.16 fixed point format.
+     */
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+    pub reserved: u16
+}
+impl ::std::default::Default for drm_color_lut {
+    fn default() -> drm_color_lut { unsafe { ::std::mem::zeroed() } }
+}
+
+pub const DRM_MODE_PAGE_FLIP_EVENT: u32 = 0x01;
+pub const DRM_MODE_PAGE_FLIP_ASYNC: u32 = 0x02;
+
+pub const DRM_MODE_ATOMIC_TEST_ONLY: u32 = 0x0100;
+pub const DRM_MODE_ATOMIC_NONBLOCK: u32 = 0x0200;
+pub const DRM_MODE_ATOMIC_ALLOW_MODESET: u32 = 0x0400;
+
+/**
+ * Lease mode resources, creating another drm_master.
+ */
+#[repr(C)]
+pub struct drm_mode_create_lease {
+	/** Pointer to array of object ids (__u32) */
+	pub object_ids: u64,
+	/** Number of object ids */
+	pub object_count: u32,
+	/** flags for new FD (O_CLOEXEC, etc) */
+	pub flags: u32,
+
+	/** Return: unique identifier for lessee. */
+	pub lessee_id: u32,
+	/** Return: file descriptor to new drm_master file */
+	pub fd: u32
+}
+impl ::std::default::Default for drm_mode_create_lease {
+    fn default() -> drm_mode_create_lease { unsafe { ::std::mem::zeroed() } }
+}
+
+/**
+ * List lesses from a drm_master
+ */
+#[repr(C)]
+pub struct drm_mode_list_lessees {
+	/** Number of lessees.
+	 * On input, provides length of the array.
+	 * On output, provides total number. No
+	 * more than the input number will be written
+	 * back, so two calls can be used to get
+	 * the size and then the data.
+	 */
+	pub count_lessees: u32,
+	pub pad: u32,
+
+	/** Pointer to lessees.
+	 * pointer to __u64 array of lessee ids
+	 */
+	pub lessees_ptr: u64
+}
+impl ::std::default::Default for drm_mode_list_lessees {
+    fn default() -> drm_mode_list_lessees { unsafe { ::std::mem::zeroed() } }
+}
+
+/**
+ * Get leased objects
+ */
+#[repr(C)]
+pub struct drm_mode_get_lease {
+	/** Number of leased objects.
+	 * On input, provides length of the array.
+	 * On output, provides total number. No
+	 * more than the input number will be written
+	 * back, so two calls can be used to get
+	 * the size and then the data.
+	 */
+	pub count_objects: u32,
+	pub pad: u32,
+
+	/** Pointer to objects.
+	 * pointer to __u32 array of object ids
+	 */
+	pub objects_ptr: u64
+}
+impl ::std::default::Default for drm_mode_get_lease {
+    fn default() -> drm_mode_get_lease { unsafe { ::std::mem::zeroed() } }
+}
+
+/**
+ * Revoke lease
+ */
+#[repr(C)]
+pub struct drm_mode_revoke_lease {
+	/** Unique ID of lessee
+	 */
+	pub lessee_id: u32
+}
+impl ::std::default::Default for drm_mode_revoke_lease {
+    fn default() -> drm_mode_revoke_lease { unsafe { ::std::mem::zeroed() } }
+}
@@ -456,15 +456,15 @@ impl ::std::default::Default for drmHashEntry {
 
 #[repr(C)]
 pub struct drmVersion {
-    version_major:      c_int,          /**< Major version */
-    version_minor:      c_int,          /**< Minor version */
-    version_patchlevel: c_int,          /**< Patch level */
-    name_len:           c_int,          /**< Length of name buffer */
-    name:               *mut c_char,    /**< Name of driver */
-    date_len:           c_int,          /**< Length of date buffer */
-    date:               *mut c_char,    /**< User-space buffer to hold date */
-    desc_len:           c_int,          /**< Length of desc buffer */
-    desc:               *mut c_char
+    pub version_major:      c_int,          /**< Major version */
+    pub version_minor:      c_int,          /**< Minor version */
+    pub version_patchlevel: c_int,          /**< Patch level */
+    pub name_len:           c_int,          /**< Length of name buffer */
+    pub name:               *mut c_char,    /**< Name of driver */
+    pub date_len:           c_int,          /**< Length of date buffer */
+    pub date:               *mut c_char,    /**< User-space buffer to hold date */
+    pub desc_len:           c_int,          /**< Length of desc buffer */
+    pub desc:               *mut c_char
 }
 impl ::std::default::Default for drmVersion {
     fn default() -> drmVersion { unsafe { ::std::mem::zeroed() } }
@@ -529,7 +529,7 @@ pub const DRM_BUS_PCI : u8 = 0;
 #[link(name = "drm")]
 #[allow(dead_code)]
 extern {
-    fn drmIoctl(fc: c_int, request: c_ulong, arg: *mut c_void) -> c_int;
+    pub fn drmIoctl(fd: c_int, request: c_ulong, arg: *mut c_void) -> c_int;
     fn drmGetHashTable() -> *mut c_void;
     fn drmGetEntry(fd: c_int) -> *mut drmHashEntry;
     /* General user-level programmer's API: unprivileged */
@@ -544,7 +544,7 @@ extern {
     pub fn drmGetCap(fd: c_int, capability: uint64_t, value: *mut uint64_t) -> c_int;
     pub fn drmFreeVersion(ptr: drmVersionPtr) -> c_void;
     pub fn drmGetMagic(fd: c_int, magic: *mut drm_magic_t) -> c_int;
-    pub fn drmGetBusid(fd: c_int) -> c_char;
+    pub fn drmGetBusid(fd: c_int) -> *mut c_char;
     pub fn drmGetInterruptFromBusID(fd: c_int, busnum: c_int, devnum: c_int, funcnum: c_int) -> c_int;
     pub fn drmGetMap(fd: c_int, idx: c_int, offset: *mut drm_handle_t,
         size: drmSizePtr, map_type: *mut drmMapType,
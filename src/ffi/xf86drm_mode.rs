@@ -84,12 +84,33 @@ pub const DRM_MODE_CONNECTOR_TV: c_int = 13;
 pub const DRM_MODE_CONNECTOR_EDP: c_int = 14;
 pub const DRM_MODE_CONNECTOR_VIRTUAL: c_int = 15;
 pub const DRM_MODE_CONNECTOR_DSI: c_int = 16;
+pub const DRM_MODE_CONNECTOR_DPI: c_int = 17;
+pub const DRM_MODE_CONNECTOR_WRITEBACK: c_int = 18;
 
 pub const DRM_MODE_PROP_PENDING: c_int = (1<<0);
 pub const DRM_MODE_PROP_RANGE: c_int = (1<<1);
 pub const DRM_MODE_PROP_IMMUTABLE: c_int = (1<<2);
 pub const DRM_MODE_PROP_ENUM: c_int = (1<<3) /* enumerated type with text strings */;
 pub const DRM_MODE_PROP_BLOB: c_int = (1<<4);
+pub const DRM_MODE_PROP_BITMASK: c_int = (1<<5) /* bitmask of enumerated types */;
+
+/* non-extended types: legacy bitmask, one bit per type: */
+pub const DRM_MODE_PROP_LEGACY_TYPE: c_int = DRM_MODE_PROP_RANGE | DRM_MODE_PROP_ENUM |
+    DRM_MODE_PROP_BLOB | DRM_MODE_PROP_BITMASK;
+
+/* extended-types: rather than continue to consume a bit per type,
+ * grab a chunk of the bits to use as integer type id.
+ */
+pub const DRM_MODE_PROP_EXTENDED_TYPE: c_int = 0x0000ffc0;
+pub const DRM_MODE_PROP_OBJECT: c_int = (1<<6);
+pub const DRM_MODE_PROP_SIGNED_RANGE: c_int = (2<<6);
+
+/* the PROP_ATOMIC flag is used to hide properties from userspace that
+ * is not aware of atomic properties.  This is mostly to work around
+ * older userspace (DDX drivers) that read/write each prop they find,
+ * witout being aware that this could be triggering a lengthy modeset.
+ */
+pub const DRM_MODE_PROP_ATOMIC: uint32_t = 0x80000000;
 
 pub const DRM_MODE_CURSOR_BO: c_int = (1<<0);
 pub const DRM_MODE_CURSOR_MOVE: c_int = (1<<1);
@@ -175,9 +196,9 @@ pub type drmModeClipPtr = *mut drmModeClip;
 
 #[repr(C)]
 pub struct drmModePropertyBlobRes {
-	id: uint32_t,
-	length: uint32_t,
-	data: *mut c_void
+	pub id: uint32_t,
+	pub length: uint32_t,
+	pub data: *mut c_void
 }
 impl ::std::default::Default for drmModePropertyBlobRes {
     fn default() -> drmModePropertyBlobRes { unsafe { ::std::mem::zeroed() } }
@@ -187,15 +208,15 @@ pub type drmModePropertyBlobPtr = *mut drmModePropertyBlobRes;
 
 #[repr(C)]
 pub struct drmModePropertyRes {
-	prop_id: uint32_t,
-	flags: uint32_t,
-	name: [c_char; DRM_PROP_NAME_LEN as usize],
-	count_values: c_int,
-	values: uint64_t, /* store the blob lengths */
-	count_enums: c_int,
-	enums: *mut drm_mode_property_enum,
-	count_blobs: c_int,
-	blob_ids: *mut uint32_t
+	pub prop_id: uint32_t,
+	pub flags: uint32_t,
+	pub name: [c_char; DRM_PROP_NAME_LEN as usize],
+	pub count_values: c_int,
+	pub values: *mut uint64_t,
+	pub count_enums: c_int,
+	pub enums: *mut drm_mode_property_enum,
+	pub count_blobs: c_int,
+	pub blob_ids: *mut uint32_t /* store the blob IDs */
 }
 impl ::std::default::Default for drmModePropertyRes {
     fn default() -> drmModePropertyRes { unsafe { ::std::mem::zeroed() } }
@@ -295,9 +316,9 @@ pub const DRM_PLANE_TYPE_CURSOR: c_int = 2;
 
 #[repr(C)]
 pub struct drmModeObjectProperties {
-	count_props: uint32_t,
-	props: *mut uint32_t,
-	prop_values: *mut uint64_t
+	pub count_props: uint32_t,
+	pub props: *mut uint32_t,
+	pub prop_values: *mut uint64_t
 }
 impl ::std::default::Default for drmModeObjectProperties {
     fn default() -> drmModeObjectProperties { unsafe { ::std::mem::zeroed() } }
@@ -307,20 +328,20 @@ pub type drmModeObjectPropertiesPtr = *mut drmModeObjectProperties;
 
 #[repr(C)]
 pub struct drmModePlane {
-	count_formats: uint32_t,
-	formats: *mut uint32_t,
-	plane_id: uint32_t,
+	pub count_formats: uint32_t,
+	pub formats: *mut uint32_t,
+	pub plane_id: uint32_t,
 
-	crtc_id: uint32_t,
-	fb_id: uint32_t,
+	pub crtc_id: uint32_t,
+	pub fb_id: uint32_t,
 
-	crtc_x: uint32_t,
-    crtc_y: uint32_t,
-	x: uint32_t,
-    y: uint32_t,
+	pub crtc_x: uint32_t,
+    pub crtc_y: uint32_t,
+	pub x: uint32_t,
+    pub y: uint32_t,
 
-	possible_crtcs: uint32_t,
-	gamma_size: uint32_t
+	pub possible_crtcs: uint32_t,
+	pub gamma_size: uint32_t
 }
 impl ::std::default::Default for drmModePlane {
     fn default() -> drmModePlane { unsafe { ::std::mem::zeroed() } }
@@ -330,8 +351,8 @@ pub type drmModePlanePtr = *mut drmModePlane;
 
 #[repr(C)]
 pub struct drmModePlaneRes {
-	count_planes: uint32_t,
-	planes: *mut uint32_t
+	pub count_planes: uint32_t,
+	pub planes: *mut uint32_t
 }
 impl ::std::default::Default for drmModePlaneRes {
     fn default() -> drmModePlaneRes { unsafe { ::std::mem::zeroed() } }
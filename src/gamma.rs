@@ -0,0 +1,195 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Legacy gamma ramp of CRTC. All channels have the same length equal to `Crtc::get_gamma_size`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GammaLut {
+    pub red: Vec<u16>,
+    pub green: Vec<u16>,
+    pub blue: Vec<u16>,
+}
+
+/// Temperature at which `GammaLut::from_temperature` does not change colors.
+pub const NEUTRAL_TEMPERATURE: u32 = 6500;
+
+/// Constructors
+impl GammaLut {
+    /// Create ramp of `size` entries with all channels zeroed.
+    pub fn new(size: usize) -> Self {
+        GammaLut { red: vec![0; size], green: vec![0; size], blue: vec![0; size] }
+    }
+
+    /// Create ramp which does not change colors.
+    pub fn identity(size: usize) -> Self {
+        GammaLut::from_fn(size, |value| (value, value, value))
+    }
+
+    /// Create ramp applying gamma correction: output is `input ^ (1 / exponent)`.
+    ///
+    /// Exponent of 1.0 gives identity, greater values make image brighter.
+    pub fn from_exponent(size: usize, exponent: f64) -> Self {
+        GammaLut::from_fn(size, |value| {
+            let value = value.powf(1.0 / exponent);
+            (value, value, value)
+        })
+    }
+
+    /// Create ramp shifting white point to color of black body of given temperature in Kelvins.
+    ///
+    /// `NEUTRAL_TEMPERATURE` gives identity, lower values give warmer (more red) image as used by
+    /// night light features.
+    pub fn from_temperature(size: usize, kelvin: u32) -> Self {
+        let (red, green, blue) = white_point(kelvin as f64);
+        let (neutral_red, neutral_green, neutral_blue) = white_point(NEUTRAL_TEMPERATURE as f64);
+        let red = (red / neutral_red).min(1.0);
+        let green = (green / neutral_green).min(1.0);
+        let blue = (blue / neutral_blue).min(1.0);
+        GammaLut::from_fn(size, |value| (value * red, value * green, value * blue))
+    }
+
+    /// Create ramp from function mapping input in range `[0, 1]` to output of each channel in
+    /// the same range.
+    pub fn from_fn<F>(size: usize, function: F) -> Self
+        where F: Fn(f64) -> (f64, f64, f64)
+    {
+        let mut lut = GammaLut::new(size);
+        for i in 0..size {
+            let input = if size > 1 { i as f64 / (size - 1) as f64 } else { 1.0 };
+            let (red, green, blue) = function(input);
+            lut.red[i] = to_u16(red);
+            lut.green[i] = to_u16(green);
+            lut.blue[i] = to_u16(blue);
+        }
+        lut
+    }
+}
+
+/// General methods
+impl GammaLut {
+    /// Get number of entries. Returns `None` if channels have different lengths.
+    pub fn size(&self) -> Option<usize> {
+        let size = self.red.len();
+        if self.green.len() == size && self.blue.len() == size {
+            Some(size)
+        } else {
+            None
+        }
+    }
+}
+
+/// Convert value in range `[0, 1]` to full range of `u16`.
+fn to_u16(value: f64) -> u16 {
+    (value.clamp(0.0, 1.0) * u16::MAX as f64).round() as u16
+}
+
+/// Approximate color of black body of given temperature, with channels in range `[0, 1]`.
+///
+/// Uses curves fitted by Tanner Helland to data by Mitchell Charity.
+fn white_point(kelvin: f64) -> (f64, f64, f64) {
+    let temperature = kelvin / 100.0;
+
+    let red = if temperature <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (temperature - 60.0).powf(-0.1332047592)
+    };
+
+    let green = if temperature <= 66.0 {
+        99.4708025861 * temperature.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (temperature - 60.0).powf(-0.0755148492)
+    };
+
+    let blue = if temperature >= 66.0 {
+        255.0
+    } else if temperature <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (temperature - 10.0).ln() - 305.0447927307
+    };
+
+    let clamp = |value: f64| value.clamp(0.0, 255.0) / 255.0;
+    (clamp(red), clamp(green), clamp(blue))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GammaLut, NEUTRAL_TEMPERATURE};
+
+    fn is_monotonic(channel: &[u16]) -> bool {
+        channel.windows(2).all(|pair| pair[0] <= pair[1])
+    }
+
+    #[test]
+    fn identity_spans_full_range() {
+        let lut = GammaLut::identity(256);
+        assert_eq!(lut.size(), Some(256));
+        assert_eq!(lut.red[0], 0);
+        assert_eq!(lut.red[255], u16::MAX);
+        assert_eq!(lut.green[128], (128.0 / 255.0 * 65535.0f64).round() as u16);
+        assert_eq!(lut.red, lut.green);
+        assert_eq!(lut.red, lut.blue);
+        assert!(is_monotonic(&lut.red));
+    }
+
+    #[test]
+    fn exponent_keeps_endpoints_and_brightens() {
+        let identity = GammaLut::identity(1024);
+        let lut = GammaLut::from_exponent(1024, 2.2);
+        assert_eq!(lut.red[0], 0);
+        assert_eq!(lut.red[1023], u16::MAX);
+        assert!(is_monotonic(&lut.red));
+        assert!(lut.red[1..1023].iter().zip(&identity.red[1..1023]).all(|(a, b)| a > b));
+        assert_eq!(GammaLut::from_exponent(1024, 1.0), identity);
+    }
+
+    #[test]
+    fn neutral_temperature_is_identity() {
+        assert_eq!(GammaLut::from_temperature(256, NEUTRAL_TEMPERATURE), GammaLut::identity(256));
+    }
+
+    #[test]
+    fn low_temperature_is_warm() {
+        let lut = GammaLut::from_temperature(256, 3000);
+        assert_eq!(lut.red[255], u16::MAX);
+        assert!(lut.green[255] < lut.red[255]);
+        assert!(lut.blue[255] < lut.green[255]);
+        assert!(is_monotonic(&lut.red) && is_monotonic(&lut.green) && is_monotonic(&lut.blue));
+    }
+
+    #[test]
+    fn degenerate_sizes() {
+        let empty = GammaLut::identity(0);
+        assert_eq!(empty.size(), Some(0));
+        assert!(empty.red.is_empty());
+
+        let single = GammaLut::from_exponent(1, 2.2);
+        assert_eq!(single.size(), Some(1));
+        assert_eq!(single.red, vec![u16::MAX]);
+    }
+
+    #[test]
+    fn size_detects_mismatched_channels() {
+        let mut lut = GammaLut::new(4);
+        lut.blue.pop();
+        assert_eq!(lut.size(), None);
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::mem;
+use std::os::unix::io::RawFd;
+
+use ffi;
+use ioctl::ioctl;
+
+/// Type of global name of buffer object created with `GemHandle::flink`.
+pub type GemName = u32;
+
+/// Handle of GEM buffer object. The handle is closed when dropped.
+///
+/// Kernel returns the same handle each time the same buffer object is imported through the same
+/// fd, so each handle must be owned by only one `GemHandle`.
+#[derive(Debug)]
+pub struct GemHandle {
+    fd: RawFd,
+    handle: u32,
+}
+
+/// General methods
+impl GemHandle {
+    /// Take ownership of `handle` created on `fd`, e.g. one returned by `drm::prime_fd_to_handle`.
+    ///
+    /// # Safety
+    ///
+    /// No other `GemHandle` may own the same handle, otherwise it is closed twice and the second
+    /// close may destroy unrelated buffer object which reused the handle.
+    pub unsafe fn from_raw(fd: RawFd, handle: u32) -> Self {
+        GemHandle { fd, handle }
+    }
+
+    /// Open buffer object by its global name.
+    ///
+    /// Returns handle together with size of the buffer object.
+    pub fn open_by_name(fd: RawFd, name: GemName) -> Result<(Self, u64), i32> {
+        let mut open = ffi::drm::drm_gem_open { name, ..Default::default() };
+        ioctl(fd, ffi::drm::DRM_IOCTL_GEM_OPEN, &mut open)?;
+        Ok((GemHandle { fd, handle: open.handle }, open.size))
+    }
+
+    /// Create dumb buffer suitable for scanout and CPU access.
+    ///
+    /// Returns handle together with pitch and size of the buffer.
+    pub fn create_dumb(fd: RawFd,
+                       width: u32,
+                       height: u32,
+                       bpp: u32)
+                       -> Result<(Self, u32, u64), i32> {
+        let mut create = ffi::drm_mode::drm_mode_create_dumb {
+            width,
+            height,
+            bpp,
+            ..Default::default()
+        };
+        ioctl(fd, ffi::drm::DRM_IOCTL_MODE_CREATE_DUMB, &mut create)?;
+        Ok((GemHandle { fd, handle: create.handle }, create.pitch, create.size))
+    }
+
+    /// Get offset for mapping dumb buffer with `mmap` on the device fd.
+    pub fn map_dumb(&self) -> Result<u64, i32> {
+        let mut map = ffi::drm_mode::drm_mode_map_dumb {
+            handle: self.handle,
+            ..Default::default()
+        };
+        ioctl(self.fd, ffi::drm::DRM_IOCTL_MODE_MAP_DUMB, &mut map)?;
+        Ok(map.offset)
+    }
+
+    /// Create global name for the buffer object which can be opened by other processes.
+    pub fn flink(&self) -> Result<GemName, i32> {
+        let mut flink = ffi::drm::drm_gem_flink { handle: self.handle, ..Default::default() };
+        ioctl(self.fd, ffi::drm::DRM_IOCTL_GEM_FLINK, &mut flink)?;
+        Ok(flink.name)
+    }
+
+    /// Release ownership of the handle without closing it.
+    pub fn into_raw(self) -> u32 {
+        let handle = self.handle;
+        mem::forget(self);
+        handle
+    }
+}
+
+/// Getters
+impl GemHandle {
+    #[inline]
+    pub fn get_handle(&self) -> u32 {
+        self.handle
+    }
+
+    /// Get fd of the device the handle belongs to.
+    #[inline]
+    pub fn get_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for GemHandle {
+    fn drop(&mut self) {
+        let mut close = ffi::drm::drm_gem_close { handle: self.handle, ..Default::default() };
+        let _ = ioctl(self.fd, ffi::drm::DRM_IOCTL_GEM_CLOSE, &mut close);
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Content protection through "Content Protection" and "HDCP Content Type" properties of
+//! connectors.
+//!
+//! Userspace requests protection by setting `ContentProtection::Desired`. Once the link is
+//! encrypted kernel changes the property to `ContentProtection::Enabled` and sends uevent. If
+//! protection is lost, it falls back to `ContentProtection::Desired`.
+
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use connector::ConnectorId;
+use connector_property::{self, EnumProperty};
+use hotplug::HotplugMonitor;
+
+/// State of content protection of connector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentProtection {
+    Undesired,
+    Desired,
+    Enabled,
+}
+
+impl EnumProperty for ContentProtection {
+    const NAME: &'static str = "Content Protection";
+
+    fn entries() -> &'static [(Self, &'static str)] {
+        &[(ContentProtection::Undesired, "Undesired"),
+          (ContentProtection::Desired, "Desired"),
+          (ContentProtection::Enabled, "Enabled")]
+    }
+}
+
+/// Type of protected content. Type 1 content may be shown only on HDCP 2.2 links.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HdcpContentType {
+    Type0,
+    Type1,
+}
+
+impl EnumProperty for HdcpContentType {
+    const NAME: &'static str = "HDCP Content Type";
+
+    fn entries() -> &'static [(Self, &'static str)] {
+        &[(HdcpContentType::Type0, "HDCP Type0"), (HdcpContentType::Type1, "HDCP Type1")]
+    }
+}
+
+/// Watcher reporting changes of content protection state of connector.
+#[derive(Debug)]
+pub struct ContentProtectionWatcher {
+    fd: RawFd,
+    connector_id: ConnectorId,
+    monitor: HotplugMonitor,
+    state: Option<ContentProtection>,
+}
+
+impl ContentProtectionWatcher {
+    /// `ContentProtectionWatcher` constructor. `fd` must stay open as long as watcher is used.
+    pub fn new(fd: RawFd, connector_id: ConnectorId) -> io::Result<Self> {
+        Ok(ContentProtectionWatcher {
+            fd,
+            connector_id,
+            monitor: HotplugMonitor::new(fd)?,
+            state: connector_property::get_connector_enum(fd, connector_id),
+        })
+    }
+
+    /// Get last known state. `None` if connector does not support content protection.
+    pub fn state(&self) -> Option<ContentProtection> {
+        self.state
+    }
+
+    /// Wait for the next change of state and return the new state.
+    ///
+    /// Blocks unless the socket, available through `AsRawFd`, was switched to non-blocking mode.
+    pub fn wait(&mut self) -> io::Result<Option<ContentProtection>> {
+        loop {
+            if !self.monitor.receive()?.concerns_connector(self.connector_id) {
+                continue;
+            }
+
+            let state = connector_property::get_connector_enum(self.fd, self.connector_id);
+            if state != self.state {
+                self.state = state;
+                return Ok(state);
+            }
+        }
+    }
+}
+
+impl AsRawFd for ContentProtectionWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.monitor.as_raw_fd()
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! HDR output through "HDR_OUTPUT_METADATA" and "Colorspace" properties of connectors.
+//!
+//! Metadata is sent to display in HDR infoframe. Both properties usually require modeset, so
+//! they should be committed with `ATOMIC_ALLOW_MODESET`.
+
+use std::os::unix::io;
+use byteorder::{ByteOrder, NativeEndian};
+
+use atomic::AtomicRequest;
+use blob::PropertyBlob;
+use connector::ConnectorId;
+use connector_property::{self, EnumProperty};
+use drm_mode;
+
+/// Size of `hdr_output_metadata` including trailing padding.
+const HDR_OUTPUT_METADATA_SIZE: usize = 32;
+
+/// Value of `metadata_type` for static metadata type 1.
+const HDMI_STATIC_METADATA_TYPE1: u8 = 0;
+
+/// Electro-optical transfer function of the content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eotf {
+    TraditionalSdr = 0,
+    TraditionalHdr = 1,
+    /// Perceptual quantizer used by HDR10.
+    SmpteSt2084 = 2,
+    /// Hybrid log-gamma.
+    Hlg = 3,
+}
+
+/// Chromaticity coordinates in units of 0.00002.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Chromaticity {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl Chromaticity {
+    /// Create from CIE 1931 coordinates in range `[0, 1]`.
+    pub fn from_f64(x: f64, y: f64) -> Self {
+        let convert = |value: f64| (value.clamp(0.0, 1.0) * 50000.0).round() as u16;
+        Chromaticity { x: convert(x), y: convert(y) }
+    }
+}
+
+/// Static HDR metadata of type 1, serialized as `hdr_output_metadata`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HdrOutputMetadata {
+    pub eotf: Eotf,
+    /// Red, green and blue primaries of mastering display.
+    pub display_primaries: [Chromaticity; 3],
+    /// White point of mastering display.
+    pub white_point: Chromaticity,
+    /// Maximum luminance of mastering display in cd/m².
+    pub max_display_mastering_luminance: u16,
+    /// Minimum luminance of mastering display in units of 0.0001 cd/m².
+    pub min_display_mastering_luminance: u16,
+    /// Maximum content light level in cd/m².
+    pub max_cll: u16,
+    /// Maximum frame-average light level in cd/m².
+    pub max_fall: u16,
+}
+
+impl HdrOutputMetadata {
+    /// Create metadata for HDR10 content mastered on display with BT.2020 primaries and D65 white
+    /// point. Light levels are left zero, meaning unknown.
+    pub fn hdr10() -> Self {
+        HdrOutputMetadata {
+            eotf: Eotf::SmpteSt2084,
+            display_primaries: [Chromaticity::from_f64(0.708, 0.292),
+                                Chromaticity::from_f64(0.170, 0.797),
+                                Chromaticity::from_f64(0.131, 0.046)],
+            white_point: Chromaticity::from_f64(0.3127, 0.3290),
+            max_display_mastering_luminance: 0,
+            min_display_mastering_luminance: 0,
+            max_cll: 0,
+            max_fall: 0,
+        }
+    }
+
+    /// Serialize as `hdr_output_metadata` suitable for property blob.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = vec![0; HDR_OUTPUT_METADATA_SIZE];
+        NativeEndian::write_u32(&mut data[0..4], HDMI_STATIC_METADATA_TYPE1 as u32);
+
+        // hdr_metadata_infoframe
+        data[4] = self.eotf as u8;
+        data[5] = HDMI_STATIC_METADATA_TYPE1;
+        let mut offset = 6;
+        for point in self.display_primaries.iter().chain(Some(&self.white_point)) {
+            NativeEndian::write_u16(&mut data[offset..offset + 2], point.x);
+            NativeEndian::write_u16(&mut data[offset + 2..offset + 4], point.y);
+            offset += 4;
+        }
+        for value in &[self.max_display_mastering_luminance,
+                       self.min_display_mastering_luminance,
+                       self.max_cll,
+                       self.max_fall] {
+            NativeEndian::write_u16(&mut data[offset..offset + 2], *value);
+            offset += 2;
+        }
+        data
+    }
+
+    /// Create property blob holding the metadata.
+    pub fn create_blob(&self, fd: io::RawFd) -> Result<PropertyBlob, i32> {
+        PropertyBlob::new(fd, &self.to_bytes())
+    }
+}
+
+/// Colorimetry signalled to display through "Colorspace" property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Colorspace {
+    Default,
+    Smpte170mYcc,
+    Bt709Ycc,
+    Xvycc601,
+    Xvycc709,
+    Sycc601,
+    OpYcc601,
+    OpRgb,
+    Bt2020Cycc,
+    Bt2020Rgb,
+    Bt2020Ycc,
+    DciP3RgbD65,
+    DciP3RgbTheater,
+    RgbWideFixed,
+    RgbWideFloat,
+    Bt601Ycc,
+}
+
+impl EnumProperty for Colorspace {
+    const NAME: &'static str = "Colorspace";
+
+    fn entries() -> &'static [(Self, &'static str)] {
+        &[(Colorspace::Default, "Default"),
+          (Colorspace::Smpte170mYcc, "SMPTE_170M_YCC"),
+          (Colorspace::Bt709Ycc, "BT709_YCC"),
+          (Colorspace::Xvycc601, "XVYCC_601"),
+          (Colorspace::Xvycc709, "XVYCC_709"),
+          (Colorspace::Sycc601, "SYCC_601"),
+          (Colorspace::OpYcc601, "opYCC_601"),
+          (Colorspace::OpRgb, "opRGB"),
+          (Colorspace::Bt2020Cycc, "BT2020_CYCC"),
+          (Colorspace::Bt2020Rgb, "BT2020_RGB"),
+          (Colorspace::Bt2020Ycc, "BT2020_YCC"),
+          (Colorspace::DciP3RgbD65, "DCI-P3_RGB_D65"),
+          (Colorspace::DciP3RgbTheater, "DCI-P3_RGB_Theater"),
+          (Colorspace::RgbWideFixed, "RGB_WIDE_FIXED"),
+          (Colorspace::RgbWideFloat, "RGB_WIDE_FLOAT"),
+          (Colorspace::Bt601Ycc, "BT601_YCC")]
+    }
+}
+
+/// Get colorspaces supported by connector.
+pub fn get_colorspaces(fd: io::RawFd, connector_id: ConnectorId) -> Vec<Colorspace> {
+    connector_property::get_connector_enum_values(fd, connector_id)
+}
+
+/// Add change of "HDR_OUTPUT_METADATA" property of connector to atomic request. `None` stops
+/// sending metadata.
+pub fn add_hdr_output_metadata(request: &mut AtomicRequest,
+                               fd: io::RawFd,
+                               connector_id: ConnectorId,
+                               metadata: Option<&PropertyBlob>)
+                               -> Result<(), i32> {
+    request.add_property_by_name(fd,
+                                 connector_id,
+                                 drm_mode::OBJECT_CONNECTOR,
+                                 "HDR_OUTPUT_METADATA",
+                                 metadata.map_or(0, |blob| blob.get_id() as u64))
+}
+
+/// Add change of "Colorspace" property of connector to atomic request.
+///
+/// Returns `-EINVAL` if connector does not support the colorspace.
+pub fn add_colorspace(request: &mut AtomicRequest,
+                      fd: io::RawFd,
+                      connector_id: ConnectorId,
+                      colorspace: Colorspace)
+                      -> Result<(), i32> {
+    connector_property::add_connector_enum(request, fd, connector_id, colorspace)
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{ByteOrder, NativeEndian};
+    use super::{Chromaticity, Eotf, HdrOutputMetadata};
+
+    #[test]
+    fn serializes_kernel_layout() {
+        let metadata = HdrOutputMetadata {
+            eotf: Eotf::SmpteSt2084,
+            display_primaries: [Chromaticity { x: 1, y: 2 },
+                                Chromaticity { x: 3, y: 4 },
+                                Chromaticity { x: 5, y: 6 }],
+            white_point: Chromaticity { x: 7, y: 8 },
+            max_display_mastering_luminance: 1000,
+            min_display_mastering_luminance: 50,
+            max_cll: 800,
+            max_fall: 400,
+        };
+        let bytes = metadata.to_bytes();
+        assert_eq!(bytes.len(), 32);
+
+        // struct hdr_output_metadata { __u32 metadata_type; struct hdr_metadata_infoframe; }
+        assert_eq!(NativeEndian::read_u32(&bytes[0..4]), 0);
+        assert_eq!(bytes[4], 2);
+        assert_eq!(bytes[5], 0);
+        let words: Vec<u16> = bytes[6..30].chunks(2).map(NativeEndian::read_u16).collect();
+        assert_eq!(words, vec![1, 2, 3, 4, 5, 6, 7, 8, 1000, 50, 800, 400]);
+        assert_eq!(&bytes[30..], &[0, 0]);
+    }
+
+    #[test]
+    fn converts_chromaticity() {
+        assert_eq!(Chromaticity::from_f64(0.3127, 0.3290), Chromaticity { x: 15635, y: 16450 });
+        assert_eq!(Chromaticity::from_f64(-1.0, 2.0), Chromaticity { x: 0, y: 50000 });
+        assert_eq!(HdrOutputMetadata::hdr10().display_primaries[0],
+                   Chromaticity { x: 35400, y: 14600 });
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Hotplug events of DRM device, read from kernel uevents.
+
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use drm;
+use uevent::{Uevent, UeventSocket};
+
+/// Hotplug event of DRM device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HotplugEvent {
+    /// State of some connectors changed. Connectors should be probed again.
+    Hotplug,
+    /// State of connector changed.
+    Connector { connector_id: u32 },
+    /// Property of connector changed, e.g. "Content Protection".
+    Property { connector_id: u32, property_id: u32 },
+    /// Lease was created or revoked.
+    Lease,
+}
+
+impl HotplugEvent {
+    /// Decode uevent of DRM device. Returns `None` for uevents which are not hotplug events.
+    pub fn from_uevent(uevent: &Uevent) -> Option<Self> {
+        if uevent.subsystem() != Some("drm") || uevent.action != "change" {
+            return None;
+        }
+
+        if uevent.get("LEASE") == Some("1") {
+            return Some(HotplugEvent::Lease);
+        }
+        if uevent.get("HOTPLUG") != Some("1") {
+            return None;
+        }
+
+        let connector = uevent.get("CONNECTOR").and_then(|value| value.parse().ok());
+        let property = uevent.get("PROPERTY").and_then(|value| value.parse().ok());
+        Some(match (connector, property) {
+            (Some(connector_id), Some(property_id)) => {
+                HotplugEvent::Property {
+                    connector_id,
+                    property_id,
+                }
+            }
+            (Some(connector_id), None) => HotplugEvent::Connector { connector_id },
+            _ => HotplugEvent::Hotplug,
+        })
+    }
+
+    /// Check if event concerns the connector. `Hotplug` concerns all connectors.
+    pub fn concerns_connector(&self, id: u32) -> bool {
+        match *self {
+            HotplugEvent::Hotplug => true,
+            HotplugEvent::Connector { connector_id } |
+            HotplugEvent::Property { connector_id, .. } => connector_id == id,
+            HotplugEvent::Lease => false,
+        }
+    }
+}
+
+/// Monitor of hotplug events of one DRM device.
+#[derive(Debug)]
+pub struct HotplugMonitor {
+    socket: UeventSocket,
+    /// Name of primary node relative to `/dev`, as reported in `DEVNAME`.
+    devname: String,
+}
+
+impl HotplugMonitor {
+    /// Monitor device `fd` was opened from. Works for primary and render nodes.
+    pub fn new(fd: RawFd) -> io::Result<Self> {
+        let path = drm::get_primary_device_name(fd).ok_or(io::ErrorKind::NotFound)?;
+        let devname = path.strip_prefix("/dev")
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .into_owned();
+        Ok(HotplugMonitor {
+            socket: UeventSocket::new()?,
+            devname,
+        })
+    }
+
+    /// Check if uevent was sent for the monitored device.
+    pub fn is_own_uevent(&self, uevent: &Uevent) -> bool {
+        uevent.get("DEVNAME") == Some(self.devname.as_str())
+    }
+
+    /// Receive next hotplug event of the device.
+    ///
+    /// Blocks unless the socket, available through `AsRawFd`, was switched to non-blocking mode.
+    pub fn receive(&self) -> io::Result<HotplugEvent> {
+        loop {
+            let uevent = self.socket.receive()?;
+            if !self.is_own_uevent(&uevent) {
+                continue;
+            }
+            if let Some(event) = HotplugEvent::from_uevent(&uevent) {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+impl AsRawFd for HotplugMonitor {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HotplugEvent;
+    use uevent::parse_uevent;
+
+    fn decode(message: &[u8]) -> Option<HotplugEvent> {
+        HotplugEvent::from_uevent(&parse_uevent(message).unwrap())
+    }
+
+    #[test]
+    fn decodes_hotplug() {
+        let message = b"change@/devices/pci0000:00/0000:00:02.0/drm/card0\0\
+                        ACTION=change\0\
+                        DEVPATH=/devices/pci0000:00/0000:00:02.0/drm/card0\0\
+                        SUBSYSTEM=drm\0\
+                        HOTPLUG=1\0\
+                        DEVNAME=dri/card0\0\
+                        DEVTYPE=drm_minor\0\
+                        SEQNUM=4321\0\
+                        MAJOR=226\0\
+                        MINOR=0\0";
+        let event = decode(message).unwrap();
+        assert_eq!(event, HotplugEvent::Hotplug);
+        assert!(event.concerns_connector(95));
+    }
+
+    #[test]
+    fn decodes_connector_and_property() {
+        let message = b"change@/devices/pci0000:00/0000:00:02.0/drm/card0\0\
+                        ACTION=change\0\
+                        DEVPATH=/devices/pci0000:00/0000:00:02.0/drm/card0\0\
+                        SUBSYSTEM=drm\0\
+                        HOTPLUG=1\0\
+                        CONNECTOR=95\0\
+                        PROPERTY=88\0\
+                        DEVNAME=dri/card0\0\
+                        DEVTYPE=drm_minor\0\
+                        SEQNUM=4322\0\
+                        MAJOR=226\0\
+                        MINOR=0\0";
+        let event = decode(message).unwrap();
+        assert_eq!(event, HotplugEvent::Property { connector_id: 95, property_id: 88 });
+        assert!(event.concerns_connector(95));
+        assert!(!event.concerns_connector(96));
+
+        let message = b"change@/devices/virtual/drm/card1\0\
+                        ACTION=change\0\
+                        DEVPATH=/devices/virtual/drm/card1\0\
+                        SUBSYSTEM=drm\0\
+                        HOTPLUG=1\0\
+                        CONNECTOR=42\0\
+                        DEVNAME=dri/card1\0";
+        assert_eq!(decode(message), Some(HotplugEvent::Connector { connector_id: 42 }));
+    }
+
+    #[test]
+    fn decodes_lease() {
+        let message = b"change@/devices/virtual/drm/card1\0\
+                        ACTION=change\0\
+                        DEVPATH=/devices/virtual/drm/card1\0\
+                        SUBSYSTEM=drm\0\
+                        LEASE=1\0\
+                        DEVNAME=dri/card1\0";
+        let event = decode(message).unwrap();
+        assert_eq!(event, HotplugEvent::Lease);
+        assert!(!event.concerns_connector(42));
+    }
+
+    #[test]
+    fn ignores_other_uevents() {
+        let message = b"add@/devices/pci0000:00/0000:00:14.0/usb1/1-1\0\
+                        ACTION=add\0\
+                        DEVPATH=/devices/pci0000:00/0000:00:14.0/usb1/1-1\0\
+                        SUBSYSTEM=usb\0\
+                        HOTPLUG=1\0\
+                        DEVNAME=bus/usb/001/002\0\
+                        DEVTYPE=usb_device\0\
+                        SEQNUM=4323\0";
+        assert_eq!(decode(message), None);
+
+        // Card being added is not a hotplug of its connectors.
+        let message = b"add@/devices/virtual/drm/card1\0\
+                        ACTION=add\0\
+                        DEVPATH=/devices/virtual/drm/card1\0\
+                        SUBSYSTEM=drm\0\
+                        DEVNAME=dri/card1\0";
+        assert_eq!(decode(message), None);
+
+        let message = b"change@/devices/virtual/drm/card1\0\
+                        ACTION=change\0\
+                        DEVPATH=/devices/virtual/drm/card1\0\
+                        SUBSYSTEM=drm\0\
+                        HOTPLUG=0\0";
+        assert_eq!(decode(message), None);
+    }
+
+    #[test]
+    fn ignores_malformed_uevents() {
+        assert_eq!(parse_uevent(b"change@/devices/virtual/drm/card0 SUBSYSTEM=drm HOTPLUG=1"),
+                   None);
+
+        // Connector id which is not a number falls back to hotplug of all connectors.
+        let message = b"change@/devices/virtual/drm/card1\0\
+                        ACTION=change\0\
+                        SUBSYSTEM=drm\0\
+                        HOTPLUG=1\0\
+                        CONNECTOR=DP-1\0";
+        assert_eq!(decode(message), Some(HotplugEvent::Hotplug));
+    }
+}
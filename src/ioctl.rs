@@ -0,0 +1,36 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use libc;
+use std::io;
+use std::os::unix::io::RawFd;
+
+use ffi;
+
+/// Issue ioctl `request` on `fd` with `arg` as argument.
+///
+/// Like libdrm mode setting functions returns negated `errno` on failure.
+pub fn ioctl<T>(fd: RawFd, request: libc::c_ulong, arg: &mut T) -> Result<(), i32> {
+    let result = unsafe {
+        ffi::xf86drm::drmIoctl(fd, request, arg as *mut T as *mut libc::c_void)
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(-io::Error::last_os_error().raw_os_error().unwrap_or(libc::EIO))
+    }
+}
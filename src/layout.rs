@@ -0,0 +1,419 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Placement of outputs in virtual desktop scanned out from single framebuffer.
+//!
+//! Outputs are positioned in logical coordinates. Logical size of output is its rotated mode size
+//! divided by its scale. The framebuffer covers whole desktop at the highest scale of all
+//! outputs, so no output has to upscale its part of the framebuffer.
+
+use libc;
+
+use connector::ConnectorId;
+use crtc::CrtcId;
+use resources::{Resources, ResourcesSnapshot};
+
+/// Rotation of output, counter-clockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Rotation {
+    /// Get value of "rotation" plane property.
+    pub fn to_property_value(&self) -> u64 {
+        match *self {
+            Rotation::Normal => 1 << 0,
+            Rotation::Rotate90 => 1 << 1,
+            Rotation::Rotate180 => 1 << 2,
+            Rotation::Rotate270 => 1 << 3,
+        }
+    }
+
+    /// Check if width and height are swapped.
+    fn is_transposed(&self) -> bool {
+        *self == Rotation::Rotate90 || *self == Rotation::Rotate270
+    }
+}
+
+/// Limits of framebuffer size reported by device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScreenLimits {
+    pub min_width: u32,
+    pub max_width: u32,
+    pub min_height: u32,
+    pub max_height: u32,
+}
+
+impl<'a> From<&'a Resources> for ScreenLimits {
+    fn from(resources: &'a Resources) -> Self {
+        ScreenLimits {
+            min_width: resources.get_min_width(),
+            max_width: resources.get_max_width(),
+            min_height: resources.get_min_height(),
+            max_height: resources.get_max_height(),
+        }
+    }
+}
+
+impl<'a> From<&'a ResourcesSnapshot> for ScreenLimits {
+    fn from(resources: &'a ResourcesSnapshot) -> Self {
+        ScreenLimits {
+            min_width: resources.min_width,
+            max_width: resources.max_width,
+            min_height: resources.min_height,
+            max_height: resources.max_height,
+        }
+    }
+}
+
+/// Output placed in layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Output {
+    pub connector_id: ConnectorId,
+    pub crtc_id: CrtcId,
+    /// Width of mode in pixels.
+    pub mode_width: u32,
+    /// Height of mode in pixels.
+    pub mode_height: u32,
+    /// Logical X-axis position in desktop. Ignored for mirrors.
+    pub x: i32,
+    /// Logical Y-axis position in desktop. Ignored for mirrors.
+    pub y: i32,
+    pub rotation: Rotation,
+    pub scale: f64,
+    /// Output whose content is mirrored. It must not mirror other output.
+    pub mirror_of: Option<ConnectorId>,
+}
+
+impl Output {
+    /// Get logical size in desktop.
+    fn logical_size(&self) -> (f64, f64) {
+        let (width, height) = if self.rotation.is_transposed() {
+            (self.mode_height, self.mode_width)
+        } else {
+            (self.mode_width, self.mode_height)
+        };
+        (width as f64 / self.scale, height as f64 / self.scale)
+    }
+}
+
+/// Layout of outputs in virtual desktop.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Layout {
+    pub outputs: Vec<Output>,
+}
+
+/// Part of framebuffer scanned out by CRTC.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    pub crtc_id: CrtcId,
+    /// Connectors driven by CRTC.
+    pub connectors: Vec<ConnectorId>,
+    pub mode_width: u32,
+    pub mode_height: u32,
+    /// Source rectangle in framebuffer. Rotation is applied when scanning it out.
+    pub src_x: u32,
+    pub src_y: u32,
+    pub src_width: u32,
+    pub src_height: u32,
+    pub rotation: Rotation,
+}
+
+/// Framebuffer size and viewports of all CRTCs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutPlan {
+    pub fb_width: u32,
+    pub fb_height: u32,
+    pub viewports: Vec<Viewport>,
+}
+
+/// General methods
+impl Layout {
+    /// `Layout` constructor.
+    pub fn new() -> Self {
+        Layout::default()
+    }
+
+    /// Add output to layout.
+    pub fn add_output(&mut self, output: Output) -> &mut Self {
+        self.outputs.push(output);
+        self
+    }
+
+    /// Find output by connector id.
+    pub fn find_output(&self, connector_id: ConnectorId) -> Option<&Output> {
+        self.outputs.iter().find(|output| output.connector_id == connector_id)
+    }
+
+    /// Compute framebuffer size and viewports.
+    ///
+    /// Desktop is moved so that its top-left corner is at the origin of the framebuffer. Mirrors
+    /// scan out the same part of framebuffer as their primary output, on shared CRTC if they
+    /// have the same id of CRTC.
+    ///
+    /// Returns `-EINVAL` if outputs are malformed, overlap, share CRTC with different modes or
+    /// rotations, if desktop is too large for framebuffer coordinates or if framebuffer size does
+    /// not fit in `limits`.
+    pub fn plan(&self, limits: &ScreenLimits) -> Result<LayoutPlan, i32> {
+        self.validate()?;
+
+        let primaries: Vec<&Output> =
+            self.outputs.iter().filter(|output| output.mirror_of.is_none()).collect();
+        if primaries.is_empty() {
+            return Err(-libc::EINVAL);
+        }
+
+        let fb_scale = self.outputs.iter().map(|output| output.scale).fold(0.0, f64::max);
+        let left = primaries.iter().map(|output| output.x).min().unwrap();
+        let top = primaries.iter().map(|output| output.y).min().unwrap();
+        let right = primaries.iter()
+            .map(|output| output.x as f64 + output.logical_size().0)
+            .fold(f64::MIN, f64::max);
+        let bottom = primaries.iter()
+            .map(|output| output.y as f64 + output.logical_size().1)
+            .fold(f64::MIN, f64::max);
+
+        let fb_width = ((right - left as f64) * fb_scale).ceil() as u32;
+        let fb_height = ((bottom - top as f64) * fb_scale).ceil() as u32;
+        if fb_width < limits.min_width || fb_width > limits.max_width ||
+           fb_height < limits.min_height || fb_height > limits.max_height {
+            return Err(-libc::EINVAL);
+        }
+
+        let mut viewports: Vec<Viewport> = Vec::new();
+        for output in &self.outputs {
+            let primary = match output.mirror_of {
+                Some(connector_id) => self.find_output(connector_id).unwrap(),
+                None => output,
+            };
+
+            if let Some(viewport) = viewports.iter_mut().find(|v| v.crtc_id == output.crtc_id) {
+                viewport.connectors.push(output.connector_id);
+                continue;
+            }
+
+            let (width, height) = primary.logical_size();
+            let offset_x = primary.x.checked_sub(left).ok_or(-libc::EINVAL)?;
+            let offset_y = primary.y.checked_sub(top).ok_or(-libc::EINVAL)?;
+            viewports.push(Viewport {
+                crtc_id: output.crtc_id,
+                connectors: vec![output.connector_id],
+                mode_width: output.mode_width,
+                mode_height: output.mode_height,
+                src_x: (offset_x as f64 * fb_scale).round() as u32,
+                src_y: (offset_y as f64 * fb_scale).round() as u32,
+                src_width: (width * fb_scale).round() as u32,
+                src_height: (height * fb_scale).round() as u32,
+                rotation: output.rotation,
+            });
+        }
+
+        Ok(LayoutPlan {
+            fb_width,
+            fb_height,
+            viewports,
+        })
+    }
+
+    /// Check consistency of outputs.
+    fn validate(&self) -> Result<(), i32> {
+        for (index, output) in self.outputs.iter().enumerate() {
+            if output.mode_width == 0 || output.mode_height == 0 || !output.scale.is_finite() ||
+               output.scale <= 0.0 {
+                return Err(-libc::EINVAL);
+            }
+            if self.outputs[..index].iter().any(|o| o.connector_id == output.connector_id) {
+                return Err(-libc::EINVAL);
+            }
+
+            let group = output.mirror_of.unwrap_or(output.connector_id);
+            if let Some(primary) = output.mirror_of {
+                match self.find_output(primary) {
+                    Some(primary) if primary.mirror_of.is_none() => {}
+                    _ => return Err(-libc::EINVAL),
+                }
+            }
+
+            for other in &self.outputs[..index] {
+                let other_group = other.mirror_of.unwrap_or(other.connector_id);
+                if other.crtc_id == output.crtc_id &&
+                   (other_group != group || other.mode_width != output.mode_width ||
+                    other.mode_height != output.mode_height || other.rotation != output.rotation) {
+                    return Err(-libc::EINVAL);
+                }
+                if other.mirror_of.is_none() && output.mirror_of.is_none() &&
+                   overlap(other, output) {
+                    return Err(-libc::EINVAL);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check if logical rectangles of two outputs overlap.
+fn overlap(first: &Output, second: &Output) -> bool {
+    let (first_width, first_height) = first.logical_size();
+    let (second_width, second_height) = second.logical_size();
+    (first.x as f64) < second.x as f64 + second_width &&
+    (second.x as f64) < first.x as f64 + first_width &&
+    (first.y as f64) < second.y as f64 + second_height &&
+    (second.y as f64) < first.y as f64 + first_height
+}
+
+#[cfg(test)]
+mod tests {
+    use libc;
+    use std::f64;
+
+    use super::{Layout, Output, Rotation, ScreenLimits, Viewport};
+
+    const LIMITS: ScreenLimits =
+        ScreenLimits { min_width: 0, max_width: 8192, min_height: 0, max_height: 8192 };
+
+    fn output(connector_id: u32, crtc_id: u32, mode: (u32, u32), x: i32, y: i32) -> Output {
+        Output {
+            connector_id,
+            crtc_id,
+            mode_width: mode.0,
+            mode_height: mode.1,
+            x,
+            y,
+            rotation: Rotation::Normal,
+            scale: 1.0,
+            mirror_of: None,
+        }
+    }
+
+    fn viewport(crtc_id: u32, connectors: &[u32], mode: (u32, u32), src: (u32, u32, u32, u32))
+                -> Viewport {
+        Viewport {
+            crtc_id,
+            connectors: connectors.to_vec(),
+            mode_width: mode.0,
+            mode_height: mode.1,
+            src_x: src.0,
+            src_y: src.1,
+            src_width: src.2,
+            src_height: src.3,
+            rotation: Rotation::Normal,
+        }
+    }
+
+    #[test]
+    fn plans_extended_desktop() {
+        let mut layout = Layout::new();
+        layout.add_output(output(1, 10, (1920, 1080), -1920, 100))
+            .add_output(output(2, 11, (1280, 1024), 0, 0));
+        let plan = layout.plan(&LIMITS).unwrap();
+        assert_eq!((plan.fb_width, plan.fb_height), (3200, 1180));
+        assert_eq!(plan.viewports,
+                   vec![viewport(10, &[1], (1920, 1080), (0, 100, 1920, 1080)),
+                        viewport(11, &[2], (1280, 1024), (1920, 0, 1280, 1024))]);
+    }
+
+    #[test]
+    fn framebuffer_uses_highest_scale() {
+        let mut hidpi = output(1, 10, (3840, 2160), 0, 0);
+        hidpi.scale = 2.0;
+        let mut layout = Layout::new();
+        layout.add_output(hidpi).add_output(output(2, 11, (1920, 1080), 1920, 0));
+        let plan = layout.plan(&LIMITS).unwrap();
+        assert_eq!((plan.fb_width, plan.fb_height), (7680, 2160));
+        assert_eq!(plan.viewports[1], viewport(11, &[2], (1920, 1080), (3840, 0, 3840, 2160)));
+    }
+
+    #[test]
+    fn plans_mirrors() {
+        let mut shared = output(2, 10, (1920, 1080), 5000, 5000);
+        shared.mirror_of = Some(1);
+        let mut separate = output(3, 11, (1280, 720), 0, 0);
+        separate.mirror_of = Some(1);
+        let mut layout = Layout::new();
+        layout.add_output(output(1, 10, (1920, 1080), 0, 0))
+            .add_output(shared)
+            .add_output(separate);
+        let plan = layout.plan(&LIMITS).unwrap();
+        assert_eq!((plan.fb_width, plan.fb_height), (1920, 1080));
+        assert_eq!(plan.viewports,
+                   vec![viewport(10, &[1, 2], (1920, 1080), (0, 0, 1920, 1080)),
+                        viewport(11, &[3], (1280, 720), (0, 0, 1920, 1080))]);
+    }
+
+    #[test]
+    fn plans_rotated_output() {
+        let mut portrait = output(1, 10, (1920, 1080), 0, 0);
+        portrait.rotation = Rotation::Rotate90;
+        let mut layout = Layout::new();
+        layout.add_output(portrait).add_output(output(2, 11, (1920, 1080), 1080, 0));
+        let plan = layout.plan(&LIMITS).unwrap();
+        assert_eq!((plan.fb_width, plan.fb_height), (3000, 1920));
+        let mut expected = viewport(10, &[1], (1920, 1080), (0, 0, 1080, 1920));
+        expected.rotation = Rotation::Rotate90;
+        assert_eq!(plan.viewports[0], expected);
+    }
+
+    #[test]
+    fn rejects_overlapping_outputs() {
+        let mut layout = Layout::new();
+        layout.add_output(output(1, 10, (1920, 1080), 0, 0))
+            .add_output(output(2, 11, (1920, 1080), 1919, 1079));
+        assert_eq!(layout.plan(&LIMITS), Err(-libc::EINVAL));
+
+        // Rotated output is taller than its mode.
+        let mut portrait = output(1, 10, (1920, 1080), 0, 0);
+        portrait.rotation = Rotation::Rotate270;
+        let mut layout = Layout::new();
+        layout.add_output(portrait).add_output(output(2, 11, (1920, 1080), 0, 1900));
+        assert_eq!(layout.plan(&LIMITS), Err(-libc::EINVAL));
+    }
+
+    #[test]
+    fn rejects_shared_crtc_with_different_rotation() {
+        let mut mirror = output(2, 10, (1920, 1080), 0, 0);
+        mirror.mirror_of = Some(1);
+        mirror.rotation = Rotation::Rotate180;
+        let mut layout = Layout::new();
+        layout.add_output(output(1, 10, (1920, 1080), 0, 0)).add_output(mirror);
+        assert_eq!(layout.plan(&LIMITS), Err(-libc::EINVAL));
+    }
+
+    #[test]
+    fn rejects_invalid_scale() {
+        for &scale in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let mut invalid = output(1, 10, (1920, 1080), 0, 0);
+            invalid.scale = scale;
+            let mut layout = Layout::new();
+            layout.add_output(invalid);
+            assert_eq!(layout.plan(&LIMITS), Err(-libc::EINVAL));
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_positions() {
+        let limits = ScreenLimits { max_width: u32::MAX, max_height: u32::MAX, ..LIMITS };
+        let mut layout = Layout::new();
+        layout.add_output(output(1, 10, (1920, 1080), i32::MIN, 0))
+            .add_output(output(2, 11, (1920, 1080), i32::MAX, 0));
+        assert_eq!(layout.plan(&limits), Err(-libc::EINVAL));
+        assert_eq!(layout.plan(&LIMITS), Err(-libc::EINVAL));
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! DRM leases, granting other process exclusive control of subset of mode objects.
+//!
+//! Lessee receives new DRM master fd which sees only leased connectors, CRTCs and planes.
+//! Displays meant to be leased, like VR headsets, are marked with "non-desktop" connector
+//! property.
+
+use std::mem;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+
+use ffi;
+use connector::ConnectorId;
+use drm_mode;
+use ioctl::ioctl;
+use property;
+
+/// Type of lessee id.
+pub type LesseeId = u32;
+
+/// Lease of mode objects. The lease is revoked when dropped.
+#[derive(Debug)]
+pub struct Lease {
+    fd: RawFd,
+    lessee_id: LesseeId,
+    lessee_fd: OwnedFd,
+}
+
+/// General methods
+impl Lease {
+    /// Lease connectors, CRTCs and planes `objects` of device `fd`, which must be DRM master.
+    /// `flags` are flags of the new fd, e.g. `libc::O_CLOEXEC`.
+    pub fn create(fd: RawFd, objects: &[u32], flags: i32) -> Result<Self, i32> {
+        let mut create = ffi::drm_mode::drm_mode_create_lease {
+            object_ids: objects.as_ptr() as u64,
+            object_count: objects.len() as u32,
+            flags: flags as u32,
+            ..Default::default()
+        };
+        ioctl(fd, ffi::drm::DRM_IOCTL_MODE_CREATE_LEASE, &mut create)?;
+        Ok(Lease {
+            fd,
+            lessee_id: create.lessee_id,
+            lessee_fd: unsafe { OwnedFd::from_raw_fd(create.fd as RawFd) },
+        })
+    }
+
+    /// Revoke the lease and close lessee fd. Copies of the fd passed to other processes lose
+    /// access to leased objects.
+    pub fn revoke(self) -> Result<(), i32> {
+        let fd = self.fd;
+        let (lessee_id, _) = self.into_raw();
+        revoke_lease(fd, lessee_id)
+    }
+
+    /// Release the lease without revoking it. Returns lessee id and lessee fd.
+    pub fn into_raw(self) -> (LesseeId, OwnedFd) {
+        let lease = mem::ManuallyDrop::new(self);
+        (lease.lessee_id, unsafe { std::ptr::read(&lease.lessee_fd) })
+    }
+}
+
+/// Getters
+impl Lease {
+    #[inline]
+    pub fn get_lessee_id(&self) -> LesseeId {
+        self.lessee_id
+    }
+
+    /// Get fd of lessee, to be passed to the process driving leased objects.
+    #[inline]
+    pub fn get_lessee_fd(&self) -> BorrowedFd<'_> {
+        self.lessee_fd.as_fd()
+    }
+}
+
+impl AsRawFd for Lease {
+    fn as_raw_fd(&self) -> RawFd {
+        self.lessee_fd.as_raw_fd()
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        let _ = revoke_lease(self.fd, self.lessee_id);
+    }
+}
+
+/// List lessees of device `fd`.
+pub fn list_lessees(fd: RawFd) -> Result<Vec<LesseeId>, i32> {
+    let mut list = ffi::drm_mode::drm_mode_list_lessees::default();
+    ioctl(fd, ffi::drm::DRM_IOCTL_MODE_LIST_LESSEES, &mut list)?;
+
+    // Kernel writes 32-bit ids despite header claiming otherwise.
+    let mut lessees = vec![0u32; list.count_lessees as usize];
+    list.lessees_ptr = lessees.as_mut_ptr() as u64;
+    ioctl(fd, ffi::drm::DRM_IOCTL_MODE_LIST_LESSEES, &mut list)?;
+    lessees.truncate(list.count_lessees as usize);
+    Ok(lessees)
+}
+
+/// Get ids of objects leased to `fd`. For DRM master which is not lessee returns all objects.
+pub fn get_lease(fd: RawFd) -> Result<Vec<u32>, i32> {
+    let mut lease = ffi::drm_mode::drm_mode_get_lease::default();
+    ioctl(fd, ffi::drm::DRM_IOCTL_MODE_GET_LEASE, &mut lease)?;
+
+    let mut objects = vec![0u32; lease.count_objects as usize];
+    lease.objects_ptr = objects.as_mut_ptr() as u64;
+    ioctl(fd, ffi::drm::DRM_IOCTL_MODE_GET_LEASE, &mut lease)?;
+    objects.truncate(lease.count_objects as usize);
+    Ok(objects)
+}
+
+/// Revoke lease of lessee `lessee_id`.
+pub fn revoke_lease(fd: RawFd, lessee_id: LesseeId) -> Result<(), i32> {
+    let mut revoke = ffi::drm_mode::drm_mode_revoke_lease { lessee_id };
+    ioctl(fd, ffi::drm::DRM_IOCTL_MODE_REVOKE_LEASE, &mut revoke)
+}
+
+/// Check if display connected to connector should not be used as part of desktop, making it
+/// candidate for leasing.
+pub fn is_non_desktop(fd: RawFd, connector_id: ConnectorId) -> bool {
+    property::find_property(fd, connector_id, drm_mode::OBJECT_CONNECTOR, "non-desktop")
+        .is_some_and(|(_, value)| value != 0)
+}
@@ -1,15 +1,49 @@
 extern crate libc;
 extern crate byteorder;
 
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "tokio")]
+extern crate futures_core;
+#[cfg(feature = "calloop")]
+extern crate calloop;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
 #[allow(dead_code)]
 pub mod ffi;
 
 mod crtc;
 mod encoder;
+mod plane;
 mod mode_info;
 mod connector;
 mod resources;
+mod gamma;
+mod color;
+mod property;
+mod atomic;
+mod blob;
+mod edid;
+mod hdr;
+mod vrr;
+mod dpms;
+mod connector_property;
+mod uevent;
+mod hotplug;
+mod hdcp;
+mod writeback;
+mod lease;
+mod snapshot;
+mod planner;
+mod layout;
+mod version;
+mod gem;
+mod ioctl;
 
+mod event;
 mod event_handler;
 
 pub mod drm;
@@ -0,0 +1,127 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std;
+
+use ffi;
+use crtc;
+use resources::Resources;
+
+/// Type of plane id.
+pub type PlaneId = u32;
+
+/// Structure representing plane.
+///
+/// Primary and cursor planes are reported only after enabling
+/// `drm::ClientCapability::UniversalPlanes`.
+pub struct Plane {
+    plane: ffi::xf86drm_mode::drmModePlanePtr,
+}
+
+/// General methods
+impl Plane {
+    /// `Plane` constructor.
+    /// Does not check if passed arguments are valid.
+    pub fn new(plane: ffi::xf86drm_mode::drmModePlanePtr) -> Self {
+        Plane { plane }
+    }
+
+    /// Get ids of CRTCs the plane can be attached to.
+    pub fn get_possible_crtc_ids(&self, resources: &Resources) -> Vec<crtc::CrtcId> {
+        resources.get_crtcs_from_mask(self.get_possible_crtcs())
+    }
+}
+
+/// Getters for original members
+impl Plane {
+    #[inline]
+    pub fn get_plane_id(&self) -> PlaneId {
+        unsafe { (*self.plane).plane_id }
+    }
+
+    /// Get id of CRTC the plane is attached to, zero if disabled.
+    #[inline]
+    pub fn get_crtc_id(&self) -> crtc::CrtcId {
+        unsafe { (*self.plane).crtc_id }
+    }
+
+    #[inline]
+    pub fn get_fb_id(&self) -> u32 {
+        unsafe { (*self.plane).fb_id }
+    }
+
+    /// Get X-axis position on the CRTC.
+    #[inline]
+    pub fn get_crtc_x(&self) -> u32 {
+        unsafe { (*self.plane).crtc_x }
+    }
+
+    /// Get Y-axis position on the CRTC.
+    #[inline]
+    pub fn get_crtc_y(&self) -> u32 {
+        unsafe { (*self.plane).crtc_y }
+    }
+
+    /// Get X-axis position on the frame buffer.
+    #[inline]
+    pub fn get_x(&self) -> u32 {
+        unsafe { (*self.plane).x }
+    }
+
+    /// Get Y-axis position on the frame buffer.
+    #[inline]
+    pub fn get_y(&self) -> u32 {
+        unsafe { (*self.plane).y }
+    }
+
+    /// Get bitmask of CRTCs the plane can be attached to, indexed by position in
+    /// `Resources::get_crtcs`.
+    #[inline]
+    pub fn get_possible_crtcs(&self) -> u32 {
+        unsafe { (*self.plane).possible_crtcs }
+    }
+
+    #[inline]
+    pub fn get_gamma_size(&self) -> u32 {
+        unsafe { (*self.plane).gamma_size }
+    }
+
+    /// Return vector of fourcc codes of supported pixel formats.
+    pub fn get_formats(&self) -> Vec<u32> {
+        let count = unsafe { (*self.plane).count_formats };
+        let mut vec = Vec::with_capacity(count as usize);
+        for pos in 0..count as isize {
+            vec.push(unsafe { *(*self.plane).formats.offset(pos) });
+        }
+        vec
+    }
+}
+
+impl Drop for Plane {
+    fn drop(&mut self) {
+        unsafe { ffi::xf86drm_mode::drmModeFreePlane(self.plane) };
+    }
+}
+
+impl std::fmt::Debug for Plane {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Plane {{ id: {}, crtc_id: {}, fb_id: {} }}",
+               self.get_plane_id(),
+               self.get_crtc_id(),
+               self.get_fb_id())
+    }
+}
@@ -0,0 +1,392 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Routing of connectors through encoders to CRTCs.
+//!
+//! Planner works on `ResourceDescription`, plain copy of the relevant parts of device resources,
+//! so it can be used on synthetic descriptions as well.
+
+use libc;
+use std::os::unix::io;
+
+use connector::ConnectorId;
+use crtc::CrtcId;
+use drm_mode;
+use encoder::EncoderId;
+use resources::decode_mask;
+
+/// Encoder as seen by planner.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncoderDescription {
+    pub encoder_id: EncoderId,
+    /// Bitmask of CRTCs indexed by position in `ResourceDescription::crtcs`.
+    pub possible_crtcs: u32,
+    /// Bitmask of encoders indexed by position in `ResourceDescription::encoders`.
+    pub possible_clones: u32,
+    /// CRTC currently driven by encoder, zero if none.
+    pub crtc_id: CrtcId,
+}
+
+/// Connector as seen by planner.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectorDescription {
+    pub connector_id: ConnectorId,
+    /// Encoders able to drive the connector.
+    pub encoders: Vec<EncoderId>,
+    /// Encoder currently driving connector, zero if none.
+    pub encoder_id: EncoderId,
+}
+
+/// Description of device resources used for planning.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceDescription {
+    /// CRTCs in order reported by device.
+    pub crtcs: Vec<CrtcId>,
+    /// Encoders in order reported by device.
+    pub encoders: Vec<EncoderDescription>,
+    pub connectors: Vec<ConnectorDescription>,
+}
+
+/// Requested output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputRequest {
+    pub connector_id: ConnectorId,
+    /// Connector whose CRTC should be shared to mirror its content. It must be requested too and
+    /// must not mirror other connector.
+    pub mirror_of: Option<ConnectorId>,
+}
+
+/// Route of one connector.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputAssignment {
+    pub connector_id: ConnectorId,
+    pub encoder_id: EncoderId,
+    pub crtc_id: CrtcId,
+}
+
+/// General methods
+impl ResourceDescription {
+    /// Describe current resources of device.
+    pub fn from_device(fd: io::RawFd) -> Option<Self> {
+        let resources = drm_mode::get_resources(fd)?;
+        let mut description = ResourceDescription {
+            crtcs: resources.get_crtcs(),
+            ..Default::default()
+        };
+
+        for encoder_id in resources.get_encoders() {
+            let encoder = drm_mode::get_encoder(fd, encoder_id)?;
+            description.encoders.push(EncoderDescription {
+                encoder_id,
+                possible_crtcs: encoder.get_possible_crtcs(),
+                possible_clones: encoder.get_possible_clones(),
+                crtc_id: encoder.get_crtc_id(),
+            });
+        }
+
+        for connector_id in resources.get_connectors() {
+            let connector = drm_mode::get_connector(fd, connector_id)?;
+            description.connectors.push(ConnectorDescription {
+                connector_id,
+                encoders: connector.get_encoders(),
+                encoder_id: connector.get_encoder_id(),
+            });
+        }
+
+        Some(description)
+    }
+
+    /// Find encoder by id.
+    fn encoder(&self, encoder_id: EncoderId) -> Option<&EncoderDescription> {
+        self.encoders.iter().find(|encoder| encoder.encoder_id == encoder_id)
+    }
+
+    /// Check if encoder can drive CRTC.
+    fn can_drive(&self, encoder: &EncoderDescription, crtc_id: CrtcId) -> bool {
+        decode_mask(&self.crtcs, encoder.possible_crtcs).contains(&crtc_id)
+    }
+
+    /// Check if two encoders can drive the same CRTC.
+    fn can_clone(&self, first: EncoderId, second: EncoderId) -> bool {
+        let ids: Vec<EncoderId> = self.encoders.iter().map(|encoder| encoder.encoder_id).collect();
+        match (self.encoder(first), self.encoder(second)) {
+            (Some(first), Some(second)) => {
+                decode_mask(&ids, first.possible_clones).contains(&second.encoder_id) &&
+                decode_mask(&ids, second.possible_clones).contains(&first.encoder_id)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// State of backtracking search for assignment.
+struct Search<'a> {
+    resources: &'a ResourceDescription,
+    requests: Vec<OutputRequest>,
+    assignments: Vec<OutputAssignment>,
+    best: Option<(usize, Vec<OutputAssignment>)>,
+}
+
+impl<'a> Search<'a> {
+    /// Assign request at `index` and all following ones. `score` is number of routes kept so far.
+    fn run(&mut self, index: usize, score: usize) {
+        if self.best.as_ref().is_some_and(|&(best, _)| best == self.requests.len()) {
+            return;
+        }
+        if index == self.requests.len() {
+            if self.best.as_ref().map_or(true, |&(best, _)| score > best) {
+                self.best = Some((score, self.assignments.clone()));
+            }
+            return;
+        }
+
+        let request = self.requests[index];
+        let resources = self.resources;
+        let connector = match resources.connectors
+            .iter()
+            .find(|connector| connector.connector_id == request.connector_id) {
+            Some(connector) => connector,
+            None => return,
+        };
+
+        // Try current encoder first so that stable solution is found early.
+        let mut encoders: Vec<EncoderId> = connector.encoders.clone();
+        encoders.sort_by_key(|&id| id != connector.encoder_id);
+
+        for encoder_id in encoders {
+            if self.assignments.iter().any(|assignment| assignment.encoder_id == encoder_id) {
+                continue;
+            }
+            let encoder = match resources.encoder(encoder_id) {
+                Some(encoder) => encoder,
+                None => continue,
+            };
+
+            let crtcs: Vec<CrtcId> = match request.mirror_of {
+                Some(primary) => {
+                    let crtc_id = self.assignments
+                        .iter()
+                        .find(|assignment| assignment.connector_id == primary)
+                        .map(|assignment| assignment.crtc_id)
+                        .unwrap();
+                    let compatible = self.assignments
+                        .iter()
+                        .filter(|assignment| assignment.crtc_id == crtc_id)
+                        .all(|assignment| resources.can_clone(assignment.encoder_id, encoder_id));
+                    if compatible { vec![crtc_id] } else { Vec::new() }
+                }
+                None => {
+                    let mut crtcs: Vec<CrtcId> = resources.crtcs
+                        .iter()
+                        .cloned()
+                        .filter(|&crtc_id| {
+                            !self.assignments.iter().any(|assignment| assignment.crtc_id == crtc_id)
+                        })
+                        .collect();
+                    crtcs.sort_by_key(|&id| id != encoder.crtc_id);
+                    crtcs
+                }
+            };
+
+            for crtc_id in crtcs {
+                if !resources.can_drive(encoder, crtc_id) {
+                    continue;
+                }
+                let kept = encoder_id == connector.encoder_id && crtc_id == encoder.crtc_id;
+                self.assignments.push(OutputAssignment {
+                    connector_id: request.connector_id,
+                    encoder_id,
+                    crtc_id,
+                });
+                self.run(index + 1, score + kept as usize);
+                self.assignments.pop();
+            }
+        }
+    }
+}
+
+/// Find conflict-free assignment of encoders and CRTCs to requested connectors.
+///
+/// Each connector gets its own encoder. Each connector not mirroring other one gets its own CRTC,
+/// mirroring connectors share CRTC of their primary connector. Among valid assignments the one
+/// keeping the most current routes is chosen.
+///
+/// Returns `-ENOENT` if requested connector is unknown, `-EINVAL` if request is malformed and
+/// `-ENOSPC` if there is no valid assignment. Assignments are returned in order of requests.
+pub fn plan_outputs(resources: &ResourceDescription,
+                    requests: &[OutputRequest])
+                    -> Result<Vec<OutputAssignment>, i32> {
+    for (index, request) in requests.iter().enumerate() {
+        if !resources.connectors.iter().any(|c| c.connector_id == request.connector_id) {
+            return Err(-libc::ENOENT);
+        }
+        if requests[..index].iter().any(|r| r.connector_id == request.connector_id) {
+            return Err(-libc::EINVAL);
+        }
+        if let Some(primary) = request.mirror_of {
+            let valid = requests.iter()
+                .any(|r| r.connector_id == primary && r.mirror_of.is_none());
+            if !valid {
+                return Err(-libc::EINVAL);
+            }
+        }
+    }
+
+    // Primary connectors go first so that mirrors find CRTC of their primary assigned.
+    let mut ordered = requests.to_vec();
+    ordered.sort_by_key(|request| request.mirror_of.is_some());
+
+    let mut search = Search {
+        resources,
+        requests: ordered,
+        assignments: Vec::new(),
+        best: None,
+    };
+    search.run(0, 0);
+
+    let (_, assignments) = search.best.ok_or(-libc::ENOSPC)?;
+    Ok(requests.iter()
+        .map(|request| {
+            *assignments.iter().find(|a| a.connector_id == request.connector_id).unwrap()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use libc;
+
+    use super::{ConnectorDescription, EncoderDescription, OutputAssignment, OutputRequest,
+                ResourceDescription, plan_outputs};
+
+    fn encoder(encoder_id: u32, possible_crtcs: u32, possible_clones: u32) -> EncoderDescription {
+        EncoderDescription { encoder_id, possible_crtcs, possible_clones, crtc_id: 0 }
+    }
+
+    fn connector(connector_id: u32, encoders: &[u32]) -> ConnectorDescription {
+        ConnectorDescription { connector_id, encoders: encoders.to_vec(), encoder_id: 0 }
+    }
+
+    fn output(connector_id: u32) -> OutputRequest {
+        OutputRequest { connector_id, mirror_of: None }
+    }
+
+    fn route(connector_id: u32, encoder_id: u32, crtc_id: u32) -> OutputAssignment {
+        OutputAssignment { connector_id, encoder_id, crtc_id }
+    }
+
+    #[test]
+    fn assigns_separate_crtcs() {
+        let resources = ResourceDescription {
+            crtcs: vec![10, 11],
+            encoders: vec![encoder(20, 0b11, 0), encoder(21, 0b11, 0)],
+            connectors: vec![connector(30, &[20]), connector(31, &[21])],
+        };
+        assert_eq!(plan_outputs(&resources, &[output(30), output(31)]),
+                   Ok(vec![route(30, 20, 10), route(31, 21, 11)]));
+    }
+
+    #[test]
+    fn conflicting_crtc_masks() {
+        // Both encoders can drive only the first CRTC.
+        let resources = ResourceDescription {
+            crtcs: vec![10, 11],
+            encoders: vec![encoder(20, 0b01, 0), encoder(21, 0b01, 0)],
+            connectors: vec![connector(30, &[20]), connector(31, &[21])],
+        };
+        assert_eq!(plan_outputs(&resources, &[output(30)]), Ok(vec![route(30, 20, 10)]));
+        assert_eq!(plan_outputs(&resources, &[output(30), output(31)]), Err(-libc::ENOSPC));
+    }
+
+    #[test]
+    fn conflicting_encoders() {
+        // Connectors share their only encoder.
+        let resources = ResourceDescription {
+            crtcs: vec![10, 11],
+            encoders: vec![encoder(20, 0b11, 0)],
+            connectors: vec![connector(30, &[20]), connector(31, &[20])],
+        };
+        assert_eq!(plan_outputs(&resources, &[output(30), output(31)]), Err(-libc::ENOSPC));
+    }
+
+    #[test]
+    fn more_connectors_than_crtcs() {
+        let resources = ResourceDescription {
+            crtcs: vec![10, 11],
+            encoders: vec![encoder(20, 0b11, 0), encoder(21, 0b11, 0), encoder(22, 0b11, 0)],
+            connectors: vec![connector(30, &[20]), connector(31, &[21]), connector(32, &[22])],
+        };
+        let requests = [output(30), output(31), output(32)];
+        assert_eq!(plan_outputs(&resources, &requests), Err(-libc::ENOSPC));
+        assert_eq!(plan_outputs(&resources, &requests[1..]),
+                   Ok(vec![route(31, 21, 10), route(32, 22, 11)]));
+    }
+
+    #[test]
+    fn backtracks_to_find_assignment() {
+        // First connector would take the first CRTC, which is the only one the second connector
+        // can use.
+        let resources = ResourceDescription {
+            crtcs: vec![10, 11],
+            encoders: vec![encoder(20, 0b11, 0), encoder(21, 0b01, 0)],
+            connectors: vec![connector(30, &[20]), connector(31, &[21])],
+        };
+        assert_eq!(plan_outputs(&resources, &[output(30), output(31)]),
+                   Ok(vec![route(30, 20, 11), route(31, 21, 10)]));
+    }
+
+    #[test]
+    fn keeps_current_routes() {
+        let mut resources = ResourceDescription {
+            crtcs: vec![10, 11],
+            encoders: vec![encoder(20, 0b11, 0), encoder(21, 0b11, 0)],
+            connectors: vec![connector(30, &[20, 21])],
+        };
+        resources.encoders[1].crtc_id = 11;
+        resources.connectors[0].encoder_id = 21;
+        assert_eq!(plan_outputs(&resources, &[output(30)]), Ok(vec![route(30, 21, 11)]));
+    }
+
+    #[test]
+    fn mirrors_share_crtc() {
+        let resources = ResourceDescription {
+            crtcs: vec![10, 11],
+            encoders: vec![encoder(20, 0b11, 0b11), encoder(21, 0b11, 0b11), encoder(22, 0b11, 0)],
+            connectors: vec![connector(30, &[20]), connector(31, &[21]), connector(32, &[22])],
+        };
+        let mirror = OutputRequest { connector_id: 31, mirror_of: Some(30) };
+        assert_eq!(plan_outputs(&resources, &[mirror, output(30)]),
+                   Ok(vec![route(31, 21, 10), route(30, 20, 10)]));
+
+        // Third encoder can not be cloned.
+        let mirror = OutputRequest { connector_id: 32, mirror_of: Some(30) };
+        assert_eq!(plan_outputs(&resources, &[output(30), mirror]), Err(-libc::ENOSPC));
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let resources = ResourceDescription {
+            crtcs: vec![10],
+            encoders: vec![encoder(20, 0b1, 0)],
+            connectors: vec![connector(30, &[20])],
+        };
+        assert_eq!(plan_outputs(&resources, &[output(31)]), Err(-libc::ENOENT));
+        assert_eq!(plan_outputs(&resources, &[output(30), output(30)]), Err(-libc::EINVAL));
+        let mirror = OutputRequest { connector_id: 30, mirror_of: Some(30) };
+        assert_eq!(plan_outputs(&resources, &[mirror]), Err(-libc::EINVAL));
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std;
+use std::ffi::CStr;
+use std::os::unix::io;
+
+use ffi;
+use drm_mode;
+
+/// Type of property id.
+pub type PropertyId = u32;
+
+/// Named value of enum or bitmask property.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyEnum {
+    pub value: u64,
+    pub name: String,
+}
+
+/// Structure representing property.
+pub struct Property {
+    property: ffi::xf86drm_mode::drmModePropertyPtr,
+}
+
+/// General methods
+impl Property {
+    /// `Property` constructor.
+    /// Does not check if passed arguments are valid.
+    pub fn new(property: ffi::xf86drm_mode::drmModePropertyPtr) -> Self {
+        Property { property }
+    }
+
+    /// Check if property has given legacy type flag, e.g. `DRM_MODE_PROP_RANGE`, or given
+    /// extended type, e.g. `DRM_MODE_PROP_SIGNED_RANGE`.
+    ///
+    /// Counterpart of `drm_property_type_is`.
+    pub fn is_type(&self, property_type: u32) -> bool {
+        let extended = ffi::xf86drm_mode::DRM_MODE_PROP_EXTENDED_TYPE as u32;
+        let flags = self.get_flags();
+        if flags & extended != 0 {
+            flags & extended == property_type
+        } else {
+            flags & property_type != 0
+        }
+    }
+
+    /// Check if property can not be changed by userspace.
+    pub fn is_immutable(&self) -> bool {
+        self.get_flags() & ffi::xf86drm_mode::DRM_MODE_PROP_IMMUTABLE as u32 != 0
+    }
+
+    /// Find value of enum entry by its name.
+    pub fn find_enum(&self, name: &str) -> Option<u64> {
+        self.get_enums().into_iter().find(|entry| entry.name == name).map(|entry| entry.value)
+    }
+}
+
+/// Getters for original members
+impl Property {
+    #[inline]
+    pub fn get_prop_id(&self) -> PropertyId {
+        unsafe { (*self.property).prop_id }
+    }
+
+    #[inline]
+    pub fn get_flags(&self) -> u32 {
+        unsafe { (*self.property).flags }
+    }
+
+    pub fn get_name(&self) -> String {
+        unsafe { CStr::from_ptr((*self.property).name.as_ptr()) }.to_string_lossy().into_owned()
+    }
+
+    /// Return vector of values. For range properties these are minimum and maximum, for enum
+    /// properties the values of entries.
+    pub fn get_values(&self) -> Vec<u64> {
+        let count = unsafe { (*self.property).count_values };
+        let mut vec = Vec::with_capacity(count as usize);
+        for pos in 0..count as isize {
+            vec.push(unsafe { *(*self.property).values.offset(pos) });
+        }
+        vec
+    }
+
+    /// Return vector of enum entries.
+    pub fn get_enums(&self) -> Vec<PropertyEnum> {
+        let count = unsafe { (*self.property).count_enums };
+        let mut vec = Vec::with_capacity(count as usize);
+        for pos in 0..count as isize {
+            let entry = unsafe { &*(*self.property).enums.offset(pos) };
+            vec.push(PropertyEnum {
+                value: entry.value,
+                name: unsafe { CStr::from_ptr(entry.name.as_ptr()) }.to_string_lossy().into_owned(),
+            });
+        }
+        vec
+    }
+}
+
+impl Drop for Property {
+    fn drop(&mut self) {
+        unsafe { ffi::xf86drm_mode::drmModeFreeProperty(self.property) };
+    }
+}
+
+impl std::fmt::Debug for Property {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Property {{ id: {}, name: {} }}", self.get_prop_id(), self.get_name())
+    }
+}
+
+/// Structure representing properties of mode object with their current values.
+pub struct ObjectProperties {
+    properties: ffi::xf86drm_mode::drmModeObjectPropertiesPtr,
+}
+
+/// General methods
+impl ObjectProperties {
+    /// `ObjectProperties` constructor.
+    /// Does not check if passed arguments are valid.
+    pub fn new(properties: ffi::xf86drm_mode::drmModeObjectPropertiesPtr) -> Self {
+        ObjectProperties { properties }
+    }
+
+    /// Find property by name. Returns the property together with its current value.
+    pub fn find(&self, fd: io::RawFd, name: &str) -> Option<(Property, u64)> {
+        for (id, value) in self.get_props().into_iter().zip(self.get_prop_values()) {
+            let property = unsafe { ffi::xf86drm_mode::drmModeGetProperty(fd, id) };
+            if property.is_null() {
+                continue;
+            }
+
+            let property = Property::new(property);
+            if property.get_name() == name {
+                return Some((property, value));
+            }
+        }
+        None
+    }
+}
+
+/// Getters for original members
+impl ObjectProperties {
+    #[inline]
+    pub fn get_count_props(&self) -> u32 {
+        unsafe { (*self.properties).count_props }
+    }
+
+    /// Return vector of property ids.
+    pub fn get_props(&self) -> Vec<PropertyId> {
+        let count = self.get_count_props();
+        let mut vec = Vec::with_capacity(count as usize);
+        for pos in 0..count as isize {
+            vec.push(unsafe { *(*self.properties).props.offset(pos) });
+        }
+        vec
+    }
+
+    /// Return vector of property values in the same order as `get_props`.
+    pub fn get_prop_values(&self) -> Vec<u64> {
+        let count = self.get_count_props();
+        let mut vec = Vec::with_capacity(count as usize);
+        for pos in 0..count as isize {
+            vec.push(unsafe { *(*self.properties).prop_values.offset(pos) });
+        }
+        vec
+    }
+}
+
+impl Drop for ObjectProperties {
+    fn drop(&mut self) {
+        unsafe { ffi::xf86drm_mode::drmModeFreeObjectProperties(self.properties) };
+    }
+}
+
+impl std::fmt::Debug for ObjectProperties {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ObjectProperties {{ props: {:?} }}", self.get_props())
+    }
+}
+
+/// Find property of mode object of given type, e.g. `OBJECT_CRTC`, by name. Returns the property
+/// together with its current value, or `None` if object does not have it.
+pub fn find_property(fd: io::RawFd,
+                     object_id: u32,
+                     object_type: u32,
+                     name: &str)
+                     -> Option<(Property, u64)> {
+    drm_mode::get_object_properties(fd, object_id, object_type)?.find(fd, name)
+}
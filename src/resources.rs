@@ -15,11 +15,25 @@
 // DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use ffi;
 use encoder;
 use connector;
 use crtc;
 
+/// Select ids whose positions have bit set in `mask`, e.g. CRTC ids from
+/// `Encoder::get_possible_crtcs` given `Resources::get_crtcs`.
+pub fn decode_mask<T: Copy>(ids: &[T], mask: u32) -> Vec<T> {
+    ids.iter()
+        .take(32)
+        .enumerate()
+        .filter(|&(index, _)| mask & (1 << index) != 0)
+        .map(|(_, &id)| id)
+        .collect()
+}
+
 /// Resources structure.
 /// Can be obtained by call to `drm_mode::get_mode_resources`
 pub struct Resources {
@@ -33,6 +47,21 @@ impl Resources {
     pub fn new(resources: ffi::xf86drm_mode::drmModeResPtr) -> Self {
         Resources { resources: resources }
     }
+
+    /// Make owned copy.
+    pub fn to_snapshot(&self) -> ResourcesSnapshot {
+        ResourcesSnapshot::from(self)
+    }
+
+    /// Convert bitmask of CRTCs, e.g. `Encoder::get_possible_crtcs`, to CRTC ids.
+    pub fn get_crtcs_from_mask(&self, mask: u32) -> Vec<crtc::CrtcId> {
+        decode_mask(&self.get_crtcs(), mask)
+    }
+
+    /// Convert bitmask of encoders, e.g. `Encoder::get_possible_clones`, to encoder ids.
+    pub fn get_encoders_from_mask(&self, mask: u32) -> Vec<encoder::EncoderId> {
+        decode_mask(&self.get_encoders(), mask)
+    }
 }
 
 /// Getters for original members
@@ -43,6 +72,16 @@ impl Resources {
         unsafe { (*self.resources).count_fbs }
     }
 
+    /// Return vector of frame buffer ids.
+    pub fn get_fbs(&self) -> Vec<u32> {
+        let count = self.get_count_fbs();
+        let mut vec = Vec::with_capacity(count as usize);
+        for pos in 0..count as isize {
+            vec.push(unsafe { *(*self.resources).fbs.offset(pos) });
+        }
+        vec
+    }
+
     /// Returns count of CRTCs
     #[inline]
     pub fn get_count_crtcs(&self) -> i32 {
@@ -90,6 +129,30 @@ impl Resources {
         }
         vec
     }
+
+    /// Returns minimal width of frame buffer.
+    #[inline]
+    pub fn get_min_width(&self) -> u32 {
+        unsafe { (*self.resources).min_width }
+    }
+
+    /// Returns maximal width of frame buffer.
+    #[inline]
+    pub fn get_max_width(&self) -> u32 {
+        unsafe { (*self.resources).max_width }
+    }
+
+    /// Returns minimal height of frame buffer.
+    #[inline]
+    pub fn get_min_height(&self) -> u32 {
+        unsafe { (*self.resources).min_height }
+    }
+
+    /// Returns maximal height of frame buffer.
+    #[inline]
+    pub fn get_max_height(&self) -> u32 {
+        unsafe { (*self.resources).max_height }
+    }
 }
 
 impl Drop for Resources {
@@ -97,3 +160,162 @@ impl Drop for Resources {
         unsafe { ffi::xf86drm_mode::drmModeFreeResources(self.resources) };
     }
 }
+
+/// Owned copy of `Resources`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResourcesSnapshot {
+    pub fbs: Vec<u32>,
+    pub crtcs: Vec<crtc::CrtcId>,
+    pub connectors: Vec<connector::ConnectorId>,
+    pub encoders: Vec<encoder::EncoderId>,
+    pub min_width: u32,
+    pub max_width: u32,
+    pub min_height: u32,
+    pub max_height: u32,
+}
+
+/// Objects added and removed between two `ResourcesSnapshot`s.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResourcesDiff {
+    pub added_fbs: Vec<u32>,
+    pub removed_fbs: Vec<u32>,
+    pub added_crtcs: Vec<crtc::CrtcId>,
+    pub removed_crtcs: Vec<crtc::CrtcId>,
+    pub added_connectors: Vec<connector::ConnectorId>,
+    pub removed_connectors: Vec<connector::ConnectorId>,
+    pub added_encoders: Vec<encoder::EncoderId>,
+    pub removed_encoders: Vec<encoder::EncoderId>,
+}
+
+impl ResourcesDiff {
+    /// Check if nothing was added or removed.
+    pub fn is_empty(&self) -> bool {
+        *self == ResourcesDiff::default()
+    }
+}
+
+/// Ids present in `new` but not in `old`.
+fn added(old: &[u32], new: &[u32]) -> Vec<u32> {
+    new.iter().filter(|id| !old.contains(id)).cloned().collect()
+}
+
+impl ResourcesSnapshot {
+    /// Compare with older snapshot, e.g. one taken before hotplug event.
+    pub fn diff(&self, old: &ResourcesSnapshot) -> ResourcesDiff {
+        ResourcesDiff {
+            added_fbs: added(&old.fbs, &self.fbs),
+            removed_fbs: added(&self.fbs, &old.fbs),
+            added_crtcs: added(&old.crtcs, &self.crtcs),
+            removed_crtcs: added(&self.crtcs, &old.crtcs),
+            added_connectors: added(&old.connectors, &self.connectors),
+            removed_connectors: added(&self.connectors, &old.connectors),
+            added_encoders: added(&old.encoders, &self.encoders),
+            removed_encoders: added(&self.encoders, &old.encoders),
+        }
+    }
+}
+
+impl<'a> From<&'a Resources> for ResourcesSnapshot {
+    fn from(resources: &'a Resources) -> Self {
+        ResourcesSnapshot {
+            fbs: resources.get_fbs(),
+            crtcs: resources.get_crtcs(),
+            connectors: resources.get_connectors(),
+            encoders: resources.get_encoders(),
+            min_width: resources.get_min_width(),
+            max_width: resources.get_max_width(),
+            min_height: resources.get_min_height(),
+            max_height: resources.get_max_height(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ResourcesDiff, ResourcesSnapshot, decode_mask};
+
+    #[test]
+    fn decodes_mask() {
+        let ids = [31, 32, 33, 34];
+        assert_eq!(decode_mask(&ids, 0), Vec::<u32>::new());
+        assert_eq!(decode_mask(&ids, 0b0001), vec![31]);
+        assert_eq!(decode_mask(&ids, 0b1010), vec![32, 34]);
+        assert_eq!(decode_mask(&ids, 0b1111), ids.to_vec());
+    }
+
+    #[test]
+    fn ignores_bits_past_end() {
+        let ids = [31, 32];
+        assert_eq!(decode_mask(&ids, 0b1110), vec![32]);
+        assert_eq!(decode_mask(&ids, u32::MAX), ids.to_vec());
+        assert_eq!(decode_mask::<u32>(&[], u32::MAX), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn ignores_ids_past_32() {
+        let ids: Vec<u32> = (0..40).collect();
+        assert_eq!(decode_mask(&ids, u32::MAX), (0..32).collect::<Vec<u32>>());
+        assert_eq!(decode_mask(&ids, 1 << 31), vec![31]);
+    }
+
+    fn snapshot(crtcs: &[u32], connectors: &[u32], encoders: &[u32]) -> ResourcesSnapshot {
+        ResourcesSnapshot {
+            crtcs: crtcs.to_vec(),
+            connectors: connectors.to_vec(),
+            encoders: encoders.to_vec(),
+            max_width: 8192,
+            max_height: 8192,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diff_of_unchanged_snapshots_is_empty() {
+        let old = snapshot(&[10, 11], &[30, 31], &[20, 21]);
+        let mut new = snapshot(&[11, 10], &[31, 30], &[20, 21]);
+        assert!(new.diff(&old).is_empty());
+
+        // Only object ids are compared.
+        new.max_width = 4096;
+        assert!(new.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_added_and_removed_objects() {
+        // MST hub plugged in, adding connector and encoder, while framebuffer was freed.
+        let mut old = snapshot(&[10, 11], &[30], &[20]);
+        old.fbs = vec![40];
+        let new = snapshot(&[10, 11], &[30, 32, 33], &[20, 22]);
+
+        let diff = new.diff(&old);
+        assert!(!diff.is_empty());
+        assert_eq!(diff,
+                   ResourcesDiff {
+                       removed_fbs: vec![40],
+                       added_connectors: vec![32, 33],
+                       added_encoders: vec![22],
+                       ..Default::default()
+                   });
+
+        let diff = old.diff(&new);
+        assert_eq!(diff,
+                   ResourcesDiff {
+                       added_fbs: vec![40],
+                       removed_connectors: vec![32, 33],
+                       removed_encoders: vec![22],
+                       ..Default::default()
+                   });
+    }
+
+    #[test]
+    fn diff_reports_replaced_objects() {
+        let old = snapshot(&[10], &[30], &[20]);
+        let new = snapshot(&[10], &[31], &[20]);
+        let diff = new.diff(&old);
+        assert_eq!(diff.added_connectors, vec![31]);
+        assert_eq!(diff.removed_connectors, vec![30]);
+        assert!(diff.added_crtcs.is_empty() && diff.removed_crtcs.is_empty());
+    }
+}
@@ -0,0 +1,489 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Snapshot of KMS state, used to restore console after application exits or crashes.
+//!
+//! Framebuffers and property blobs are referenced by id, so restoring works only while they
+//! exist, e.g. framebuffer of fbdev console.
+
+use libc;
+use std::ffi::CStr;
+use std::os::unix::io;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use ffi;
+use atomic::AtomicRequest;
+use blob::PropertyBlob;
+use connector::ConnectorId;
+use crtc::CrtcId;
+use drm_mode;
+use gamma::GammaLut;
+use mode_info::ModeInfo;
+use plane::PlaneId;
+use property;
+
+/// Properties of planes recorded in snapshot.
+static PLANE_PROPERTIES: [&str; 13] = ["FB_ID", "CRTC_ID", "SRC_X", "SRC_Y", "SRC_W", "SRC_H",
+                                       "CRTC_X", "CRTC_Y", "CRTC_W", "CRTC_H", "rotation", "zpos",
+                                       "alpha"];
+
+/// Properties of connectors recorded in snapshot.
+static CONNECTOR_PROPERTIES: [&str; 9] = ["Broadcast RGB", "max bpc", "Colorspace",
+                                          "scaling mode", "underscan", "underscan hborder",
+                                          "underscan vborder", "content type", "aspect ratio"];
+
+/// Properties of CRTCs recorded in snapshot.
+static CRTC_PROPERTIES: [&str; 1] = ["VRR_ENABLED"];
+
+/// Owned copy of `ModeInfo`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ModeSnapshot {
+    pub clock: u32,
+    pub hdisplay: u16,
+    pub hsync_start: u16,
+    pub hsync_end: u16,
+    pub htotal: u16,
+    pub hskew: u16,
+    pub vdisplay: u16,
+    pub vsync_start: u16,
+    pub vsync_end: u16,
+    pub vtotal: u16,
+    pub vscan: u16,
+    pub vrefresh: u32,
+    pub flags: u32,
+    pub mode_type: u32,
+    pub name: String,
+}
+
+impl ModeSnapshot {
+    /// Convert back to `ModeInfo`. Name longer than the kernel limit is truncated.
+    pub fn to_mode_info(&self) -> ModeInfo {
+        let mut raw = ffi::xf86drm_mode::drmModeModeInfo {
+            clock: self.clock,
+            hdisplay: self.hdisplay,
+            hsync_start: self.hsync_start,
+            hsync_end: self.hsync_end,
+            htotal: self.htotal,
+            hskew: self.hskew,
+            vdisplay: self.vdisplay,
+            vsync_start: self.vsync_start,
+            vsync_end: self.vsync_end,
+            vtotal: self.vtotal,
+            vscan: self.vscan,
+            vrefresh: self.vrefresh,
+            flags: self.flags,
+            mode_type: self.mode_type,
+            ..Default::default()
+        };
+        let length = self.name.len().min(raw.name.len() - 1);
+        for (dst, src) in raw.name.iter_mut().zip(&self.name.as_bytes()[..length]) {
+            *dst = *src as libc::c_char;
+        }
+        ModeInfo::new(raw)
+    }
+}
+
+impl<'a> From<&'a ModeInfo> for ModeSnapshot {
+    fn from(mode: &'a ModeInfo) -> Self {
+        let raw = unsafe { &*mode.as_ptr() };
+        let name = if raw.name.contains(&0) {
+            unsafe { CStr::from_ptr(raw.name.as_ptr()) }.to_string_lossy().into_owned()
+        } else {
+            String::new()
+        };
+        ModeSnapshot {
+            clock: raw.clock,
+            hdisplay: raw.hdisplay,
+            hsync_start: raw.hsync_start,
+            hsync_end: raw.hsync_end,
+            htotal: raw.htotal,
+            hskew: raw.hskew,
+            vdisplay: raw.vdisplay,
+            vsync_start: raw.vsync_start,
+            vsync_end: raw.vsync_end,
+            vtotal: raw.vtotal,
+            vscan: raw.vscan,
+            vrefresh: raw.vrefresh,
+            flags: raw.flags,
+            mode_type: raw.mode_type,
+            name,
+        }
+    }
+}
+
+/// State of CRTC.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CrtcSnapshot {
+    pub crtc_id: CrtcId,
+    pub fb_id: u32,
+    pub x: u32,
+    pub y: u32,
+    /// Current mode, `None` if CRTC is disabled.
+    pub mode: Option<ModeSnapshot>,
+    /// Connectors driven by the CRTC.
+    pub connectors: Vec<ConnectorId>,
+    pub gamma: Option<GammaLut>,
+}
+
+/// State of plane as values of its properties.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlaneSnapshot {
+    pub plane_id: PlaneId,
+    /// Pairs of property name and value.
+    pub properties: Vec<(String, u64)>,
+}
+
+/// Value of property of mode object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PropertySnapshot {
+    pub object_id: u32,
+    /// Type of object, e.g. `OBJECT_CONNECTOR`.
+    pub object_type: u32,
+    pub name: String,
+    pub value: u64,
+}
+
+/// Snapshot of state of all CRTCs, planes and selected connector and CRTC properties.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KmsSnapshot {
+    pub crtcs: Vec<CrtcSnapshot>,
+    pub planes: Vec<PlaneSnapshot>,
+    pub properties: Vec<PropertySnapshot>,
+}
+
+/// Read values of properties of mode object which exist on it. Immutable properties are skipped,
+/// as kernel rejects atomic requests changing them even to their current value.
+fn read_properties(fd: io::RawFd,
+                   object_id: u32,
+                   object_type: u32,
+                   names: &[&str])
+                   -> Vec<(String, u64)> {
+    let properties = match drm_mode::get_object_properties(fd, object_id, object_type) {
+        Some(properties) => properties,
+        None => return Vec::new(),
+    };
+    names.iter()
+        .filter_map(|name| properties.find(fd, name))
+        .filter(|(property, _)| !property.is_immutable())
+        .map(|(property, value)| (property.get_name(), value))
+        .collect()
+}
+
+/// General methods
+impl KmsSnapshot {
+    /// Record current state of device.
+    ///
+    /// Plane state is recorded only if `drm::ClientCapability::Atomic` is enabled.
+    pub fn capture(fd: io::RawFd) -> Result<Self, i32> {
+        let resources = drm_mode::get_resources(fd).ok_or(-libc::ENOENT)?;
+
+        // Map connectors to CRTCs through their current encoders.
+        let mut routes = Vec::new();
+        for connector_id in resources.get_connectors() {
+            let crtc_id = drm_mode::get_connector(fd, connector_id)
+                .and_then(|connector| drm_mode::get_encoder(fd, connector.get_encoder_id()))
+                .map_or(0, |encoder| encoder.get_crtc_id());
+            if crtc_id != 0 {
+                routes.push((connector_id, crtc_id));
+            }
+        }
+
+        let mut snapshot = KmsSnapshot {
+            crtcs: Vec::new(),
+            planes: Vec::new(),
+            properties: Vec::new(),
+        };
+
+        for crtc_id in resources.get_crtcs() {
+            let crtc = drm_mode::get_crtc(fd, crtc_id).ok_or(-libc::ENOENT)?;
+            let gamma = if crtc.get_gamma_size() > 0 {
+                drm_mode::get_gamma(fd, crtc_id).ok()
+            } else {
+                None
+            };
+            snapshot.crtcs.push(CrtcSnapshot {
+                crtc_id,
+                fb_id: crtc.get_buffer_id(),
+                x: crtc.get_x(),
+                y: crtc.get_y(),
+                mode: if crtc.get_mode_valid() {
+                    Some(ModeSnapshot::from(&crtc.get_mode()))
+                } else {
+                    None
+                },
+                connectors: routes.iter()
+                    .filter(|&&(_, id)| id == crtc_id)
+                    .map(|&(connector_id, _)| connector_id)
+                    .collect(),
+                gamma,
+            });
+
+            for (name, value) in read_properties(fd, crtc_id, drm_mode::OBJECT_CRTC,
+                                                 &CRTC_PROPERTIES) {
+                snapshot.properties.push(PropertySnapshot {
+                    object_id: crtc_id,
+                    object_type: drm_mode::OBJECT_CRTC,
+                    name,
+                    value,
+                });
+            }
+        }
+
+        for connector_id in resources.get_connectors() {
+            for (name, value) in read_properties(fd, connector_id, drm_mode::OBJECT_CONNECTOR,
+                                                 &CONNECTOR_PROPERTIES) {
+                snapshot.properties.push(PropertySnapshot {
+                    object_id: connector_id,
+                    object_type: drm_mode::OBJECT_CONNECTOR,
+                    name,
+                    value,
+                });
+            }
+        }
+
+        for plane_id in drm_mode::get_plane_resources(fd).unwrap_or_default() {
+            let properties = read_properties(fd, plane_id, drm_mode::OBJECT_PLANE,
+                                             &PLANE_PROPERTIES);
+            if !properties.is_empty() {
+                snapshot.planes.push(PlaneSnapshot { plane_id, properties });
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Reapply recorded state through legacy interface. Plane state is not restored.
+    ///
+    /// Every CRTC, gamma ramp and property is attempted even if some fail, so that as much of
+    /// the state as possible is restored. Returns the first error.
+    pub fn restore(&self, fd: io::RawFd) -> Result<(), i32> {
+        let mut result = Ok(());
+        for crtc in &self.crtcs {
+            let crtc_result = match crtc.mode {
+                Some(ref mode) => {
+                    drm_mode::set_crtc(fd,
+                                       crtc.crtc_id,
+                                       crtc.fb_id,
+                                       crtc.x,
+                                       crtc.y,
+                                       &crtc.connectors,
+                                       &mode.to_mode_info())
+                }
+                None => drm_mode::disable_crtc(fd, crtc.crtc_id),
+            };
+            result = result.and(crtc_result);
+        }
+        result.and(self.restore_gamma_and_properties(fd))
+    }
+
+    /// Reapply recorded state in single atomic commit, including planes.
+    ///
+    /// Requires `drm::ClientCapability::Atomic`. Stops at first error.
+    pub fn restore_atomic(&self, fd: io::RawFd) -> Result<(), i32> {
+        let mut request = AtomicRequest::new();
+        let mut blobs = Vec::new();
+
+        let resources = drm_mode::get_resources(fd).ok_or(-libc::ENOENT)?;
+        for connector_id in resources.get_connectors() {
+            let crtc_id = self.crtcs
+                .iter()
+                .find(|crtc| crtc.connectors.contains(&connector_id))
+                .map_or(0, |crtc| crtc.crtc_id);
+            request.add_property_by_name(fd,
+                                         connector_id,
+                                         drm_mode::OBJECT_CONNECTOR,
+                                         "CRTC_ID",
+                                         crtc_id as u64)?;
+        }
+
+        for crtc in &self.crtcs {
+            let mode_id = match crtc.mode {
+                Some(ref mode) => {
+                    let blob = PropertyBlob::from_mode(fd, &mode.to_mode_info())?;
+                    let id = blob.get_id();
+                    blobs.push(blob);
+                    id
+                }
+                None => 0,
+            };
+            request.add_property_by_name(fd,
+                                         crtc.crtc_id,
+                                         drm_mode::OBJECT_CRTC,
+                                         "MODE_ID",
+                                         mode_id as u64)?;
+            request.add_property_by_name(fd,
+                                         crtc.crtc_id,
+                                         drm_mode::OBJECT_CRTC,
+                                         "ACTIVE",
+                                         crtc.mode.is_some() as u64)?;
+        }
+
+        for plane in &self.planes {
+            for &(ref name, value) in &plane.properties {
+                request.add_property_by_name(fd,
+                                             plane.plane_id,
+                                             drm_mode::OBJECT_PLANE,
+                                             name,
+                                             value)?;
+            }
+        }
+
+        drm_mode::atomic_commit(fd, &request, drm_mode::ATOMIC_ALLOW_MODESET, 0)?;
+        self.restore_gamma_and_properties(fd)
+    }
+
+    /// Reapply gamma ramps and recorded properties. Continues after failure and returns the first
+    /// error.
+    fn restore_gamma_and_properties(&self, fd: io::RawFd) -> Result<(), i32> {
+        let mut result = Ok(());
+        for crtc in &self.crtcs {
+            if let Some(ref gamma) = crtc.gamma {
+                result = result.and(drm_mode::set_gamma(fd, crtc.crtc_id, gamma));
+            }
+        }
+
+        for property in &self.properties {
+            let found = property::find_property(fd,
+                                                property.object_id,
+                                                property.object_type,
+                                                &property.name);
+            let property_result = match found {
+                Some((_, current)) if current == property.value => Ok(()),
+                Some((found, _)) => {
+                    drm_mode::set_object_property(fd,
+                                                  property.object_id,
+                                                  property.object_type,
+                                                  found.get_prop_id(),
+                                                  property.value)
+                }
+                None => Err(-libc::ENOENT),
+            };
+            result = result.and(property_result);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libc;
+
+    use ffi;
+    use mode_info::ModeInfo;
+    use super::ModeSnapshot;
+
+    fn mode(name: &str) -> ModeSnapshot {
+        ModeSnapshot {
+            clock: 148500,
+            hdisplay: 1920,
+            hsync_start: 2008,
+            hsync_end: 2052,
+            htotal: 2200,
+            hskew: 0,
+            vdisplay: 1080,
+            vsync_start: 1084,
+            vsync_end: 1089,
+            vtotal: 1125,
+            vscan: 0,
+            vrefresh: 60,
+            flags: 0x5,
+            mode_type: 0x48,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn mode_round_trips() {
+        let snapshot = mode("1920x1080");
+        let mode_info = snapshot.to_mode_info();
+        let raw = unsafe { &*mode_info.as_ptr() };
+        assert_eq!((raw.hdisplay, raw.vdisplay, raw.clock), (1920, 1080, 148500));
+        assert_eq!(ModeSnapshot::from(&mode_info), snapshot);
+    }
+
+    #[test]
+    fn long_mode_name_is_truncated() {
+        let name: String = "0123456789".repeat(4);
+        let mode_info = mode(&name).to_mode_info();
+        let raw = unsafe { &*mode_info.as_ptr() };
+        assert_eq!(raw.name[31], 0);
+        assert_eq!(ModeSnapshot::from(&mode_info).name, &name[..31]);
+    }
+
+    #[test]
+    fn mode_name_without_nul_is_empty() {
+        let mut raw = ffi::xf86drm_mode::drmModeModeInfo { hdisplay: 640, ..Default::default() };
+        for byte in raw.name.iter_mut() {
+            *byte = b'x' as libc::c_char;
+        }
+        let snapshot = ModeSnapshot::from(&ModeInfo::new(raw));
+        assert_eq!(snapshot.name, "");
+        assert_eq!(snapshot.hdisplay, 640);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_round_trips_through_json() {
+        use serde_json;
+
+        use drm_mode;
+        use gamma::GammaLut;
+        use snapshot::{CrtcSnapshot, KmsSnapshot, PlaneSnapshot, PropertySnapshot};
+
+        let snapshot = KmsSnapshot {
+            crtcs: vec![CrtcSnapshot {
+                            crtc_id: 41,
+                            fb_id: 90,
+                            x: 0,
+                            y: 0,
+                            mode: Some(mode("1920x1080")),
+                            connectors: vec![77],
+                            gamma: Some(GammaLut::identity(4)),
+                        },
+                        CrtcSnapshot {
+                            crtc_id: 42,
+                            fb_id: 0,
+                            x: 0,
+                            y: 0,
+                            mode: None,
+                            connectors: Vec::new(),
+                            gamma: None,
+                        }],
+            planes: vec![PlaneSnapshot {
+                             plane_id: 31,
+                             properties: vec![("FB_ID".to_string(), 90),
+                                              ("CRTC_ID".to_string(), 41)],
+                         }],
+            properties: vec![PropertySnapshot {
+                                 object_id: 77,
+                                 object_type: drm_mode::OBJECT_CONNECTOR,
+                                 name: "max bpc".to_string(),
+                                 value: 8,
+                             }],
+        };
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let restored: KmsSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, snapshot);
+    }
+}
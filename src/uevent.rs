@@ -0,0 +1,208 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Kernel uevents read from `NETLINK_KOBJECT_UEVENT` socket, without depending on libudev.
+
+use libc;
+use std::collections::HashMap;
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+/// Size of buffer used for receiving uevents. Kernel limits environment of uevent to 2048 bytes.
+const BUFFER_SIZE: usize = 8192;
+
+/// Multicast group of uevents sent by kernel.
+const KERNEL_GROUP: u32 = 1;
+
+/// Kernel uevent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Uevent {
+    /// Action, e.g. `change`.
+    pub action: String,
+    /// Path of device in sysfs, relative to `/sys`.
+    pub devpath: String,
+    /// All `KEY=value` pairs of the event, including `ACTION` and `DEVPATH`.
+    pub properties: HashMap<String, String>,
+}
+
+impl Uevent {
+    /// Get value of property.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(|value| value.as_str())
+    }
+
+    /// Get subsystem of device.
+    pub fn subsystem(&self) -> Option<&str> {
+        self.get("SUBSYSTEM")
+    }
+}
+
+/// Decode uevent message as sent by kernel: `action@devpath` header followed by `KEY=value`
+/// pairs, all separated by null bytes.
+///
+/// Returns `None` for malformed messages, including messages without any `KEY=value` pair, and
+/// for messages in libudev format.
+pub fn parse_uevent(message: &[u8]) -> Option<Uevent> {
+    let mut fields = message.split(|&byte| byte == 0).filter(|field| !field.is_empty());
+    let header = String::from_utf8_lossy(fields.next()?);
+    let separator = header.find('@')?;
+
+    let mut properties = HashMap::new();
+    for field in fields {
+        let field = String::from_utf8_lossy(field);
+        if let Some(separator) = field.find('=') {
+            properties.insert(field[..separator].to_string(), field[separator + 1..].to_string());
+        }
+    }
+    if properties.is_empty() {
+        return None;
+    }
+
+    Some(Uevent {
+        action: properties.get("ACTION")
+            .cloned()
+            .unwrap_or_else(|| header[..separator].to_string()),
+        devpath: properties.get("DEVPATH")
+            .cloned()
+            .unwrap_or_else(|| header[separator + 1..].to_string()),
+        properties,
+    })
+}
+
+/// Socket receiving uevents broadcast by kernel.
+#[derive(Debug)]
+pub struct UeventSocket {
+    fd: OwnedFd,
+}
+
+impl UeventSocket {
+    /// Open socket and subscribe to kernel uevents.
+    pub fn new() -> io::Result<Self> {
+        let fd = unsafe {
+            libc::socket(libc::AF_NETLINK,
+                         libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                         libc::NETLINK_KOBJECT_UEVENT)
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        address.nl_groups = KERNEL_GROUP;
+        let result = unsafe {
+            libc::bind(fd.as_raw_fd(),
+                       &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                       mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t)
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(UeventSocket { fd })
+    }
+
+    /// Receive next uevent. Messages not sent by kernel and malformed messages are skipped.
+    ///
+    /// Blocks unless socket was switched to non-blocking mode.
+    pub fn receive(&self) -> io::Result<Uevent> {
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        loop {
+            let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+            let mut length = mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t;
+            let result = unsafe {
+                libc::recvfrom(self.fd.as_raw_fd(),
+                               buffer.as_mut_ptr() as *mut libc::c_void,
+                               buffer.len(),
+                               0,
+                               &mut address as *mut libc::sockaddr_nl as *mut libc::sockaddr,
+                               &mut length)
+            };
+            if result < 0 {
+                let error = io::Error::last_os_error();
+                if error.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(error);
+            }
+
+            // Anyone can send to the group, only trust messages from kernel.
+            if address.nl_pid != 0 {
+                continue;
+            }
+            if let Some(uevent) = parse_uevent(&buffer[..result as usize]) {
+                return Ok(uevent);
+            }
+        }
+    }
+}
+
+impl AsRawFd for UeventSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_uevent;
+
+    const DRM_HOTPLUG: &[u8] = b"change@/devices/pci0000:00/0000:00:02.0/drm/card0\0\
+                                 ACTION=change\0\
+                                 DEVPATH=/devices/pci0000:00/0000:00:02.0/drm/card0\0\
+                                 SUBSYSTEM=drm\0\
+                                 HOTPLUG=1\0\
+                                 DEVNAME=dri/card0\0\
+                                 DEVTYPE=drm_minor\0\
+                                 SEQNUM=4321\0\
+                                 MAJOR=226\0\
+                                 MINOR=0\0";
+
+    #[test]
+    fn parses_kernel_uevent() {
+        let uevent = parse_uevent(DRM_HOTPLUG).unwrap();
+        assert_eq!(uevent.action, "change");
+        assert_eq!(uevent.devpath, "/devices/pci0000:00/0000:00:02.0/drm/card0");
+        assert_eq!(uevent.subsystem(), Some("drm"));
+        assert_eq!(uevent.get("HOTPLUG"), Some("1"));
+        assert_eq!(uevent.get("DEVNAME"), Some("dri/card0"));
+        assert_eq!(uevent.get("CONNECTOR"), None);
+        assert_eq!(uevent.properties.len(), 9);
+    }
+
+    #[test]
+    fn keeps_value_with_separator() {
+        let uevent = parse_uevent(b"add@/module/foo\0ACTION=add\0PARAMS=a=b\0\0").unwrap();
+        assert_eq!(uevent.get("PARAMS"), Some("a=b"));
+        assert_eq!(uevent.devpath, "/module/foo");
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        assert_eq!(parse_uevent(b""), None);
+        assert_eq!(parse_uevent(b"\0\0"), None);
+        // Message without null separators.
+        assert_eq!(parse_uevent(b"change@/devices/virtual/drm/card0 ACTION=change SUBSYSTEM=drm"),
+                   None);
+        assert_eq!(parse_uevent(b"change@/devices/virtual/drm/card0\0"), None);
+        assert_eq!(parse_uevent(b"ACTION=change\0SUBSYSTEM=drm\0"), None);
+        // Messages rebroadcast by udev start with "libudev" magic instead of header.
+        assert_eq!(parse_uevent(b"libudev\0\xfe\xed\xca\xfe\0ACTION=change\0"), None);
+    }
+}
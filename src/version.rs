@@ -0,0 +1,72 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use libc::{c_char, c_int};
+use std::slice;
+
+use ffi;
+
+/// Version of driver or library.
+///
+/// Unlike `drmVersion` it owns its strings, so it does not need to be freed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    pub major: i32,
+    pub minor: i32,
+    pub patchlevel: i32,
+    /// Name of the driver, e.g. "i915" or "amdgpu".
+    pub name: String,
+    pub date: String,
+    pub desc: String,
+}
+
+impl Version {
+    /// Copy contents of `drmVersion` and free it.
+    ///
+    /// Returns `None` if `version` is null.
+    ///
+    /// # Safety
+    ///
+    /// Non-null `version` must have been returned by libdrm and must not be used afterwards.
+    pub unsafe fn from_raw(version: ffi::xf86drm::drmVersionPtr) -> Option<Self> {
+        if version.is_null() {
+            return None;
+        }
+
+        let result = Version {
+            major: (*version).version_major,
+            minor: (*version).version_minor,
+            patchlevel: (*version).version_patchlevel,
+            name: to_string((*version).name, (*version).name_len),
+            date: to_string((*version).date, (*version).date_len),
+            desc: to_string((*version).desc, (*version).desc_len),
+        };
+        ffi::xf86drm::drmFreeVersion(version);
+        Some(result)
+    }
+}
+
+/// Copy string of given length which is not necessarily null-terminated.
+unsafe fn to_string(ptr: *const c_char, len: c_int) -> String {
+    if ptr.is_null() || len <= 0 {
+        return String::new();
+    }
+
+    let bytes = slice::from_raw_parts(ptr as *const u8, len as usize);
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
@@ -0,0 +1,62 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Variable refresh rate through "vrr_capable" property of connectors and "VRR_ENABLED" property
+//! of CRTCs.
+
+use libc;
+use std::os::unix::io;
+
+use atomic::AtomicRequest;
+use connector::ConnectorId;
+use crtc::CrtcId;
+use drm_mode;
+use property;
+
+/// Check if display connected to connector supports variable refresh rate.
+pub fn is_vrr_capable(fd: io::RawFd, connector_id: ConnectorId) -> bool {
+    property::find_property(fd, connector_id, drm_mode::OBJECT_CONNECTOR, "vrr_capable")
+        .is_some_and(|(_, value)| value != 0)
+}
+
+/// Check if variable refresh rate is enabled on CRTC.
+pub fn is_vrr_enabled(fd: io::RawFd, crtc_id: CrtcId) -> bool {
+    property::find_property(fd, crtc_id, drm_mode::OBJECT_CRTC, "VRR_ENABLED")
+        .is_some_and(|(_, value)| value != 0)
+}
+
+/// Enable or disable variable refresh rate on CRTC without atomic request.
+///
+/// Returns `-ENOENT` if driver does not support variable refresh rate.
+pub fn set_vrr_enabled(fd: io::RawFd, crtc_id: CrtcId, enabled: bool) -> Result<(), i32> {
+    let (property, _) = property::find_property(fd, crtc_id, drm_mode::OBJECT_CRTC, "VRR_ENABLED")
+        .ok_or(-libc::ENOENT)?;
+    drm_mode::set_object_property(fd,
+                                  crtc_id,
+                                  drm_mode::OBJECT_CRTC,
+                                  property.get_prop_id(),
+                                  enabled as u64)
+}
+
+/// Add change of "VRR_ENABLED" property of CRTC to atomic request.
+pub fn add_vrr_enabled(request: &mut AtomicRequest,
+                       fd: io::RawFd,
+                       crtc_id: CrtcId,
+                       enabled: bool)
+                       -> Result<(), i32> {
+    request.add_property_by_name(fd, crtc_id, drm_mode::OBJECT_CRTC, "VRR_ENABLED", enabled as u64)
+}
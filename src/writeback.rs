@@ -0,0 +1,81 @@
+// Copyright 2016 The libdrm-rs project developers
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software
+// and associated documentation files (the "Software"), to deal in the Software without
+// restriction, including without limitation the rights to use, copy, modify, merge, publish,
+// distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all copies or
+// substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING
+// BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND
+// NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM,
+// DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+//! Capture of composed output of CRTC into framebuffer through writeback connectors.
+//!
+//! Writeback connectors are exposed only after enabling `drm::ClientCapability::Atomic` and
+//! `drm::ClientCapability::WritebackConnectors`. The connector is attached to CRTC like any other
+//! connector, and each commit which sets "WRITEBACK_FB_ID" writes one frame.
+
+use libc;
+use std::os::unix::io;
+use byteorder::{ByteOrder, NativeEndian};
+
+use atomic::{AtomicRequest, OutFence};
+use blob;
+use connector::ConnectorId;
+use crtc::CrtcId;
+use drm::{self, ClientCapability};
+use drm_mode;
+use property;
+
+/// Enable client capabilities needed to use writeback connectors.
+pub fn enable_writeback_connectors(fd: io::RawFd) -> Result<(), i32> {
+    drm::set_client_cap(fd, ClientCapability::Atomic, 1)?;
+    drm::set_client_cap(fd, ClientCapability::WritebackConnectors, 1)
+}
+
+/// Get fourcc codes of pixel formats supported as target of writeback connector.
+pub fn get_writeback_pixel_formats(fd: io::RawFd, connector_id: ConnectorId) -> Option<Vec<u32>> {
+    let (_, blob_id) = property::find_property(fd,
+                                               connector_id,
+                                               drm_mode::OBJECT_CONNECTOR,
+                                               "WRITEBACK_PIXEL_FORMATS")?;
+    let data = blob::get_property_blob(fd, blob_id as u32)?;
+    Some(data.chunks(4).filter(|chunk| chunk.len() == 4).map(NativeEndian::read_u32).collect())
+}
+
+/// Add capture of output of CRTC into framebuffer `fb_id` to atomic request.
+///
+/// Returns out-fence signalled once the frame is written. Take it from the request with
+/// `AtomicRequest::take_out_fence` after commit.
+pub fn attach_writeback(request: &mut AtomicRequest,
+                        fd: io::RawFd,
+                        connector_id: ConnectorId,
+                        crtc_id: CrtcId,
+                        fb_id: u32)
+                        -> Result<OutFence, i32> {
+    let find = |name| {
+        property::find_property(fd, connector_id, drm_mode::OBJECT_CONNECTOR, name)
+            .ok_or(-libc::ENOENT)
+    };
+    let (crtc, _) = find("CRTC_ID")?;
+    let (fb, _) = find("WRITEBACK_FB_ID")?;
+    let (fence, _) = find("WRITEBACK_OUT_FENCE_PTR")?;
+
+    request.add_property(connector_id, crtc.get_prop_id(), crtc_id as u64)?;
+    request.add_property(connector_id, fb.get_prop_id(), fb_id as u64)?;
+    request.add_out_fence(connector_id, fence.get_prop_id())
+}
+
+/// Add detaching of writeback connector from its CRTC to atomic request.
+pub fn detach_writeback(request: &mut AtomicRequest,
+                        fd: io::RawFd,
+                        connector_id: ConnectorId)
+                        -> Result<(), i32> {
+    request.add_property_by_name(fd, connector_id, drm_mode::OBJECT_CONNECTOR, "CRTC_ID", 0)
+}